@@ -2,6 +2,7 @@ use internal::*;
 use debug::*;
 
 pub fn build(compiler: &Data, top: &Data) -> Status<Data> {
+    let _session = SourceMap::session();
     let build = map!();
 
     if let Some(pipeline) = confirm!(compiler.index(&keyword!("pipeline"))) {
@@ -18,6 +19,6 @@ pub fn build(compiler: &Data, top: &Data) -> Status<Data> {
     return success!(build);
 }
 
-pub fn call_build(compiler: &Data, top: &Data) -> Status<Data> {
-    return build(compiler, top);
+pub fn call_build(compiler: &Data, top: &Data, build_map: &Data) -> Status<Data> {
+    return Diagnostic::report(build(compiler, top), compiler, build_map, format_shared!("failed to build"));
 }
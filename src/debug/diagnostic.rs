@@ -0,0 +1,522 @@
+use internal::*;
+use debug::*;
+
+static RESET: &'static str = "\x1b[0m";
+static BOLD: &'static str = "\x1b[1m";
+static BLUE: &'static str = "\x1b[1;34m";
+
+fn json_list(items: Vec<String>) -> String {
+    return format!("[{}]", items.join(","));
+}
+
+fn json_strings(strings: &Vec<SharedString>) -> Status<String> {
+    let mut items = Vec::new();
+    for string in strings.iter() {
+        items.push(confirm!(json_string(string)));
+    }
+    return success!(json_list(items));
+}
+
+fn paint(text: &str, code: &str, color: bool) -> String {
+    match color {
+        true => return format!("{}{}{}", code, text, RESET),
+        false => return String::from(text),
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Label {
+    pub position:   Position,
+    pub message:    Option<SharedString>,
+    pub primary:    bool,
+}
+
+impl Label {
+
+    pub fn new(position: Position, message: Option<SharedString>, primary: bool) -> Self {
+        Self {
+            position:   position,
+            message:    message,
+            primary:    primary,
+        }
+    }
+
+    fn last_index(&self) -> usize {
+        match self.position.end > self.position.start {
+            true => return self.position.end - 1,
+            false => return self.position.start,
+        }
+    }
+
+    fn serialize(&self) -> Data {
+        let mut map = Map::new();
+        map.insert(identifier!("positions"), list!(vector![self.position.serialize()]));
+        map.insert(identifier!("primary"), boolean!(self.primary));
+        if let Some(message) = &self.message {
+            map.insert(identifier!("message"), string!(String, message.clone()));
+        }
+        return map!(map);
+    }
+
+    // file, line, column and length of the label as json fields
+    fn location_json(&self) -> Status<String> {
+        let file = match self.position.file_name() {
+            Some(name) => confirm!(json_string(&name)),
+            None => String::from("null"),
+        };
+        return success!(format!("\"file\":{},\"line\":{},\"column\":{},\"length\":{}", file, self.position.line(), self.position.character(), self.position.length()));
+    }
+
+    fn to_json(&self) -> Status<String> {
+        let message = match &self.message {
+            Some(message) => confirm!(json_string(message)),
+            None => String::from("null"),
+        };
+        return success!(format!("{{{},\"message\":{},\"primary\":{}}}", confirm!(self.location_json()), message, self.primary));
+    }
+
+    fn lines(&self) -> (usize, usize) {
+        let first = SourceMap::line_character(self.position.file, self.position.start).0;
+        let last = SourceMap::line_character(self.position.file, self.last_index()).0;
+        return (first, last);
+    }
+
+    // returns the first column and the width of the underline on the given line, of which the first cut characters are not shown
+    fn columns(&self, line: usize, line_length: usize, cut: usize) -> Option<(usize, usize)> {
+        let (first_line, first_character) = SourceMap::line_character(self.position.file, self.position.start);
+        let (last_line, last_character) = SourceMap::line_character(self.position.file, self.last_index());
+
+        if line < first_line || line > last_line {
+            return None;
+        }
+
+        let from = match line == first_line {
+            true => first_character,
+            false => 1,
+        };
+
+        let to = match line == last_line {
+            true => last_character,
+            false => line_length + cut,
+        };
+
+        if from <= cut && to <= cut {
+            return None;
+        }
+
+        let (from, to) = (from.max(cut + 1) - cut, to.saturating_sub(cut));
+        return Some((from, (to + 1).max(from + 1) - from));
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Diagnostic {
+    pub severity:   SharedString,
+    pub kind:       SharedString,
+    pub message:    SharedString,
+    pub tags:       Vec<SharedString>,
+    pub labels:     Vec<Label>,
+    pub notes:      Vec<SharedString>,
+    pub help:       Vec<SharedString>,
+}
+
+impl Diagnostic {
+
+    pub fn new(severity: &str, message: SharedString) -> Self {
+        Self {
+            severity:   SharedString::from(severity),
+            kind:       SharedString::from(severity),
+            message:    message,
+            tags:       Vec::new(),
+            labels:     Vec::new(),
+            notes:      Vec::new(),
+            help:       Vec::new(),
+        }
+    }
+
+    // positions of the error become labels, positions of sources that are not registered anymore become notes
+    pub fn from_error(error: Error, root: &Option<&Data>, build: &Data) -> Self {
+        let mut tags = Vec::new();
+        let mut positions = Vec::new();
+        let mut unresolved = Vec::new();
+        let mut error = error;
+
+        loop {
+            match error {
+                Error::Tag(tag, inner) => {
+                    tags.push(tag.serialize());
+                    error = *inner;
+                }
+                Error::Positioned(serialized, inner) => {
+                    if let Data::List(serialized) = serialized {
+                        for position in serialized.iter() {
+                            match Position::deserialize(position) {
+                                Status::Success(position) => positions.push(position),
+                                Status::Error(..) => unresolved.push(format_shared!("at {} of a source that is not available anymore", position.serialize())),
+                            }
+                        }
+                    }
+                    error = *inner;
+                }
+                Error::Reported(inner) => error = *inner,
+                other => {
+                    error = other;
+                    break;
+                }
+            }
+        }
+
+        let kind = error.kind();
+        let mut diagnostic = Diagnostic::new("error", error.display(root, build));
+        diagnostic.kind = SharedString::from(kind);
+        diagnostic.tags = tags;
+        diagnostic.notes = unresolved;
+        if !positions.is_empty() {
+            diagnostic.add_label(&positions, None, true);
+        }
+        return diagnostic;
+    }
+
+    fn reported(error: &Error) -> bool {
+        match error {
+            Error::Tag(_, inner) | Error::Positioned(_, inner) => return Diagnostic::reported(inner),
+            Error::Reported(..) => return true,
+            _other => return false,
+        }
+    }
+
+    // failed steps are recorded as error diagnostics and emitted if the compiler asks for diagnostics, the caller then only learns that the step failed
+    // errors of nested steps pass through, so every error is only reported once
+    pub fn report<T>(status: Status<T>, compiler: &Data, build: &Data, failed: SharedString) -> Status<T> {
+        match status {
+            Status::Error(error) if !Diagnostic::reported(&error) => {
+                let diagnostic = Diagnostic::from_error(error.clone(), &Some(compiler), build);
+                confirm!(diagnostic.append(build));
+
+                if let Some(json) = confirm!(Diagnostic::output_format(compiler)) {
+                    confirm!(diagnostic.emit(json));
+                    return error!(Reported, Error::Message(string!(String, failed)));
+                }
+                return error!(Reported, error);
+            }
+            other => return other,
+        }
+    }
+
+    pub fn from_token(token: &Token, root: &Option<&Data>, build: &Data) -> Option<Self> {
+        if let TokenType::Invalid(error) = &token.token_type {
+            let mut diagnostic = Diagnostic::from_error(error.clone(), root, build);
+            diagnostic.add_label(&token.position, None, true);
+            return Some(diagnostic);
+        }
+        return None;
+    }
+
+    pub fn from_note(note: &Note) -> Self {
+        let mut diagnostic = Diagnostic::new("note", note.message.clone());
+        diagnostic.kind = match &note.kind {
+            Data::Identifier(kind) | Data::Keyword(kind) => kind.clone(),
+            other => other.serialize(),
+        };
+        diagnostic.add_label(&vec![note.position.clone()], None, true);
+        return diagnostic;
+    }
+
+    // positions without a line, like those on lines a streamed source already dropped, can not be shown and become notes
+    pub fn add_label(&mut self, positions: &Vec<Position>, message: Option<SharedString>, primary: bool) {
+        for (index, position) in positions.iter().enumerate() {
+            if position.line() == 0 {
+                self.notes.push(format_shared!("at {} of a source that is not available anymore", position.serialize().serialize()));
+                continue;
+            }
+            let message = (index == positions.len() - 1).then_some(message.clone()).flatten();
+            self.labels.push(Label::new(position.clone(), message, primary));
+        }
+    }
+
+    pub fn add_note(&mut self, note: SharedString) {
+        self.notes.push(note);
+    }
+
+    pub fn add_help(&mut self, help: SharedString) {
+        self.help.push(help);
+    }
+
+    fn optional_strings(serialized: &Data, key: &str) -> Status<Vec<SharedString>> {
+        let mut strings = Vec::new();
+        if let Some(list) = confirm!(serialized.index(&identifier!(key))) {
+            for string in unpack_list!(&list).iter() {
+                strings.push(unpack_string!(string));
+            }
+        }
+        return success!(strings);
+    }
+
+    pub fn deserialize(serialized: &Data) -> Status<Self> {
+        ensure!(serialized.is_map(), ExpectedFound, expected_list!["map"], serialized.clone());
+
+        let message = confirm!(serialized.index(&identifier!("message")));
+        let message = expect!(message, string!("diagnostic may not miss the message field"));
+        let message = unpack_string!(&message);
+
+        let severity = match confirm!(serialized.index(&identifier!("severity"))) {
+            Some(severity) => unpack_identifier!(&severity).printable(),
+            None => String::from("error"),
+        };
+
+        let mut diagnostic = Diagnostic::new(&severity, message);
+        if let Some(kind) = confirm!(serialized.index(&identifier!("kind"))) {
+            diagnostic.kind = unpack_identifier!(&kind);
+        }
+
+        // a diagnostic carrying its source uses positions relative to it, like the ones of a token stream
+        let file = match confirm!(serialized.index(&identifier!("source"))) {
+            Some(source) => {
+                let name = match confirm!(serialized.index(&identifier!("file"))) {
+                    Some(name) => Some(unpack_string!(&name)),
+                    None => None,
+                };
+                Some(SourceMap::register(&name, &unpack_string!(&source)))
+            },
+            None => None,
+        };
+
+        if let Some(labels) = confirm!(serialized.index(&identifier!("labels"))) {
+            for (index, label) in unpack_list!(&labels).iter().enumerate() {
+                let positions = confirm!(label.index(&identifier!("positions")));
+                let positions = expect!(positions, string!("label may not miss the positions field"));
+
+                let mut deserialized = Vec::new();
+                for position in unpack_list!(&positions).iter() {
+                    match file {
+                        Some(file) => deserialized.push(confirm!(Position::deserialize_partial(position, file))),
+                        None => deserialized.push(confirm!(Position::deserialize(position))),
+                    }
+                }
+
+                let message = match confirm!(label.index(&identifier!("message"))) {
+                    Some(message) => Some(unpack_string!(&message)),
+                    None => None,
+                };
+
+                let primary = match confirm!(label.index(&identifier!("primary"))) {
+                    Some(primary) => unpack_boolean!(&primary),
+                    None => index == 0,
+                };
+
+                diagnostic.add_label(&deserialized, message, primary);
+            }
+        }
+
+        diagnostic.tags = confirm!(Diagnostic::optional_strings(serialized, "tags"));
+        diagnostic.notes.extend(confirm!(Diagnostic::optional_strings(serialized, "notes")));
+        diagnostic.help = confirm!(Diagnostic::optional_strings(serialized, "help"));
+        return success!(diagnostic);
+    }
+
+    fn strings(strings: &Vec<SharedString>) -> Data {
+        return list!(strings.iter().map(|string| string!(String, string.clone())).collect());
+    }
+
+    pub fn serialize(&self) -> Data {
+        let mut map = Map::new();
+        map.insert(identifier!("severity"), identifier!(String, self.severity.clone()));
+        map.insert(identifier!("kind"), identifier!(String, self.kind.clone()));
+        map.insert(identifier!("message"), string!(String, self.message.clone()));
+        map.insert(identifier!("tags"), Diagnostic::strings(&self.tags));
+        map.insert(identifier!("labels"), list!(self.labels.iter().map(|label| label.serialize()).collect()));
+        map.insert(identifier!("notes"), Diagnostic::strings(&self.notes));
+        map.insert(identifier!("help"), Diagnostic::strings(&self.help));
+        return map!(map);
+    }
+
+    fn lint_listed(lints: &Data, list: &str, kind: &SharedString) -> Status<bool> {
+        if let Some(codes) = confirm!(lints.index(&keyword!(list))) {
+            let kind = identifier!(String, kind.clone());
+            return success!(unpack_list!(&codes).iter().any(|code| *code == kind));
+        }
+        return success!(false);
+    }
+
+    fn append(&self, build: &Data) -> Status<()> {
+        let selector = keyword!("diagnostics");
+        let mut diagnostics = match confirm!(build.index(&selector)) {
+            Some(diagnostics) => unpack_list!(&diagnostics),
+            None => SharedVector::new(),
+        };
+        diagnostics.push(self.serialize());
+        confirm!(build.modify(Some(&selector), list!(diagnostics)));
+        return success!(());
+    }
+
+    // adds the diagnostic to the build, unless the compiler allows it. denied warnings abort compilation
+    pub fn record(mut self, root: &Data, build: &Data) -> Status<()> {
+        if let Some(lints) = confirm!(root.index(&keyword!("lints"))) {
+            if confirm!(Diagnostic::lint_listed(&lints, "allow", &self.kind)) {
+                return success!(());
+            }
+
+            if self.severity.printable() == "warning" {
+                let deny_warnings = match confirm!(lints.index(&keyword!("deny_warnings"))) {
+                    Some(deny_warnings) => unpack_boolean!(&deny_warnings),
+                    None => false,
+                };
+
+                if deny_warnings || confirm!(Diagnostic::lint_listed(&lints, "deny", &self.kind)) {
+                    self.severity = SharedString::from("error");
+                }
+            }
+        }
+
+        confirm!(self.append(build));
+
+        if self.severity.printable() == "error" {
+            let error = Error::Message(string!("denied warning {}: {}", self.kind, self.message));
+            return match self.labels.iter().find(|label| label.primary).or(self.labels.first()) {
+                Some(label) => error!(Positioned, error, list!(vector![label.position.serialize()])),
+                None => Status::Error(error),
+            };
+        }
+        return success!(());
+    }
+
+    pub fn to_json(&self) -> Status<SharedString> {
+        let primary = self.labels.iter().find(|label| label.primary).or(self.labels.first());
+        let location = match primary {
+            Some(label) => confirm!(label.location_json()),
+            None => String::from("\"file\":null,\"line\":null,\"column\":null,\"length\":null"),
+        };
+
+        let mut labels = Vec::new();
+        for label in self.labels.iter() {
+            labels.push(confirm!(label.to_json()));
+        }
+
+        let json = format!("{{\"severity\":{},\"kind\":{},\"message\":{},{},\"tags\":{},\"labels\":{},\"notes\":{},\"help\":{}}}",
+            confirm!(json_string(&self.severity)), confirm!(json_string(&self.kind)), confirm!(json_string(&self.message)), location,
+            confirm!(json_strings(&self.tags)), json_list(labels), confirm!(json_strings(&self.notes)), confirm!(json_strings(&self.help)));
+        return success!(SharedString::from(&json));
+    }
+
+    // #text or #json from the compiler, none if the compiler does not configure diagnostics
+    pub fn output_format(compiler: &Data) -> Status<Option<bool>> {
+        match confirm!(compiler.index(&keyword!("diagnostics"))) {
+            Some(format) => match unpack_keyword!(&format).printable().as_str() {
+                "json" => return success!(Some(true)),
+                "text" => return success!(Some(false)),
+                _other => return error!(string!("diagnostics must be either #text or #json")),
+            },
+            None => return success!(None),
+        }
+    }
+
+    pub fn emit(&self, json: bool) -> Status<()> {
+        match json {
+            true => println!("{}", confirm!(self.to_json()).unicode()),
+            false => print!("{}", self.render(false).unicode()),
+        }
+        return success!(());
+    }
+
+    fn severity_color(&self) -> &'static str {
+        match self.severity.printable().as_str() {
+            "error" => return "\x1b[1;31m",
+            "warning" => return "\x1b[1;33m",
+            "note" => return "\x1b[1;36m",
+            _other => return "\x1b[1;32m",
+        }
+    }
+
+    fn render_file(&self, file: usize, gutter_width: usize, color: bool, output: &mut String) {
+        let padding = " ".repeat(gutter_width);
+        let gutter = paint(&format!("{} |", padding), BLUE, color);
+        let labels: Vec<&Label> = self.labels.iter().filter(|label| label.position.file == file).collect();
+
+        let primary = labels.iter().find(|label| label.primary).unwrap_or(&labels[0]);
+        let (line, character) = SourceMap::line_character(file, primary.position.start);
+        let name = SourceMap::name(file).map(|name| name.printable()).unwrap_or(String::from("string"));
+        output.push_str(&format!("{}{} {}:{}:{}\n", padding, paint("-->", BLUE, color), name, line, character));
+        output.push_str(&format!("{}\n", gutter));
+
+        let mut lines = Vec::new();
+        for label in labels.iter() {
+            let (first, last) = label.lines();
+            for line in first..last + 1 {
+                if !lines.contains(&line) {
+                    lines.push(line);
+                }
+            }
+        }
+        lines.sort();
+
+        let mut previous = None;
+        for line in lines.into_iter() {
+            if let Some(previous) = previous {
+                if line > previous + 1 {
+                    output.push_str(&format!("{}\n", paint("...", BLUE, color)));
+                }
+            }
+            previous = Some(line);
+
+            let text = SourceMap::line(file, line);
+            let cut = SourceMap::line_cut(file, line);
+            output.push_str(&format!("{} {}\n", paint(&format!("{:>width$} |", line, width = gutter_width), BLUE, color), text.unicode()));
+
+            for label in labels.iter() {
+                if let Some((from, width)) = label.columns(line, text.len(), cut) {
+                    let (marker, code) = match label.primary {
+                        true => ("^", self.severity_color()),
+                        false => ("-", BLUE),
+                    };
+
+                    let mut marker_line = format!("{} {}{}", gutter, " ".repeat(from - 1), paint(&marker.repeat(width), code, color));
+                    if label.lines().1 == line {
+                        if let Some(message) = &label.message {
+                            marker_line.push_str(&format!(" {}", paint(&message.unicode(), code, color)));
+                        }
+                    }
+                    output.push_str(&format!("{}\n", marker_line));
+                }
+            }
+        }
+    }
+
+    pub fn render(&self, color: bool) -> SharedString {
+        let mut output = String::new();
+        output.push_str(&paint(&self.severity.printable(), self.severity_color(), color));
+        let mut message = String::new();
+        for tag in self.tags.iter() {
+            message.push_str(&format!("{} -> ", tag.unicode()));
+        }
+        message.push_str(&self.message.unicode());
+        output.push_str(&paint(&format!(": {}", message), BOLD, color));
+        output.push('\n');
+
+        let gutter_width = self.labels.iter().map(|label| label.lines().1).max().unwrap_or(1).to_string().len();
+        let mut files = Vec::new();
+        for label in self.labels.iter() {
+            if !files.contains(&label.position.file) {
+                files.push(label.position.file);
+            }
+        }
+
+        for file in files.into_iter() {
+            self.render_file(file, gutter_width, color, &mut output);
+        }
+
+        let padding = " ".repeat(gutter_width);
+        if !self.labels.is_empty() && (!self.notes.is_empty() || !self.help.is_empty()) {
+            output.push_str(&format!("{}\n", paint(&format!("{} |", padding), BLUE, color)));
+        }
+
+        for note in self.notes.iter() {
+            output.push_str(&format!("{} {} {}: {}\n", padding, paint("=", BLUE, color), paint("note", BOLD, color), note.unicode()));
+        }
+
+        for help in self.help.iter() {
+            output.push_str(&format!("{} {} {}: {}\n", padding, paint("=", BLUE, color), paint("help", BOLD, color), help.unicode()));
+        }
+
+        return SharedString::from(&output);
+    }
+}
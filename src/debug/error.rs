@@ -7,6 +7,8 @@ use super::comma_seperated_list;
 #[allow(dead_code)]
 pub enum Error {
     Tag(Data, Box<Error>),
+    Positioned(Data, Box<Error>),
+    Reported(Box<Error>),
     Message(Data),
     InvalidItemCount(Data, Data),
     InvalidCondition(Data),
@@ -58,6 +60,21 @@ pub enum Error {
     ExpectedWordFound(Data),
     InvalidNumberSystem(Data),
     AmbiguousIdentifier(Data),
+    InconsistentIndentation(Data),
+    IntegerOverflow(Data),
+    FloatOverflow(Data),
+}
+
+// line and character of the first position, they are resolved while serializing so the source does not have to be alive anymore
+fn location(positions: &Data) -> SharedString {
+    if let Data::List(positions) = positions {
+        if let Some(position) = positions.iter().next() {
+            if let (Status::Success(Some(line)), Status::Success(Some(character))) = (position.index(&identifier!("line")), position.index(&identifier!("character"))) {
+                return format_shared!("{}:{}", line.serialize(), character.serialize());
+            }
+        }
+    }
+    return SharedString::from("unknown position");
 }
 
 impl Error {
@@ -65,6 +82,8 @@ impl Error {
     pub fn display(self, root: &Option<&Data>, build: &Data) -> SharedString {
         match self {
             Error::Tag(tag, error)                                 => return format_hook!(root, build, "tag", vector![tag, string!(String, error.display(root, build))], "{} -> {}", tag.serialize(), error.display(root, build)),
+            Error::Positioned(positions, error)                    => return format_hook!(root, build, "positioned", vector![positions.clone(), string!(String, error.display(root, build))], "{} -> {}", location(&positions), error.display(root, build)),
+            Error::Reported(error)                                 => return error.display(root, build),
             Error::Message(message)                                => return format_hook!(root, build, "message", vector![message], "{}", extract_string!(&message)),
             Error::InvalidItemCount(specified, received)           => return format_hook!(root, build, "invalid_item_count", vector![specified, received], "{} items specified; found {}", extract_integer!(&specified), extract_integer!(&received)),
             Error::InvalidCondition(condition)                     => return format_hook!(root, build, "invalid_condition", vector![condition], "invalid condition {}", extract_keyword!(&condition)),
@@ -116,6 +135,71 @@ impl Error {
             Error::ExpectedWordFound(found)                        => return format_hook!(root, build, "expected_word_found", vector![found], "expected word; found {}", found.serialize()), // DEBUG SERIALIZE (?)
             Error::InvalidNumberSystem(system)                     => return format_hook!(root, build, "invalid_number_system", vector![system], "invalid number system {}", extract_identifier!(system)),
             Error::AmbiguousIdentifier(identifier)                 => return format_hook!(root, build, "ambiguous_identifier", vector![identifier], "ambiguous identifier {}; could be identifier and type identifier", extract_identifier!(identifier)),
+            Error::InconsistentIndentation(indentation)            => return format_hook!(root, build, "inconsistent_indentation", vector![indentation], "inconsistent indentation {}; does not match any enclosing level", indentation.serialize()),
+            Error::IntegerOverflow(literal)                        => return format_hook!(root, build, "integer_overflow", vector![literal], "integer literal {} does not fit into 64 bits", literal.serialize()),
+            Error::FloatOverflow(literal)                          => return format_hook!(root, build, "float_overflow", vector![literal], "float literal {} is too large for 64 bits", literal.serialize()),
+        }
+    }
+
+    pub fn kind(&self) -> &'static str {
+        match self {
+            Error::Tag(..)                        => return "tag",
+            Error::Positioned(..)                 => return "positioned",
+            Error::Reported(error)                => return error.kind(),
+            Error::Message(..)                    => return "message",
+            Error::InvalidItemCount(..)           => return "invalid_item_count",
+            Error::InvalidCondition(..)           => return "invalid_condition",
+            Error::UnexpectedToken(..)            => return "unexpected_token",
+            Error::InvalidToken(..)               => return "invalid_token",
+            Error::InvalidTokenType(..)           => return "invalid_token_type",
+            Error::InvalidLocation(..)            => return "invalid_location",
+            Error::Expected(..)                   => return "expected",
+            Error::ExpectedFound(..)              => return "expected_found",
+            Error::InvalidType(..)                => return "invalid_type",
+            Error::InvalidPieceType(..)           => return "invalid_piece_type",
+            Error::UnregisteredCharacter(..)      => return "unregistered_character",
+            Error::DuplicateSignature(..)         => return "duplicate_signature",
+            Error::DuplicateBreaking(..)          => return "duplicate_breaking",
+            Error::DuplicateNonBreaking(..)       => return "duplicate_non_breaking",
+            Error::ExpectedIdentifierType(..)     => return "expected_identifier_type",
+            Error::EmptyLiteral                   => return "emtpy_literal",
+            Error::InvalidCharacterLength(..)     => return "invalid_character_length",
+            Error::InvalidPathLength(..)          => return "invalid_path_length",
+            Error::NothingToParse                 => return "nothing_to_parse",
+            Error::NoPreviousReturn               => return "no_previous_return",
+            Error::InvalidVariadic(..)            => return "invalid_variadic",
+            Error::UnexpectedCompilerFunction(..) => return "unexpected_compiler_function",
+            Error::ExpectedCondition              => return "expected_condition",
+            Error::ExpectedConditionFound(..)     => return "expected_condition_found",
+            Error::ExpectedParameter(..)          => return "expected_parameter",
+            Error::ExpectedParameterFound(..)     => return "expected_parameter_found",
+            Error::UnexpectedParameter(..)        => return "unexpected_parameter",
+            Error::UnterminatedEscapeSequence     => return "unterminated_escape_sequence",
+            Error::InvalidEscapeSequence(..)      => return "invalid_escape_sequence",
+            Error::ExpectedReturn(..)             => return "expected_return",
+            Error::ExpectedReturnFound(..)        => return "expected_return_found",
+            Error::InexplicitOverwrite(..)        => return "inexplicit_overwrite",
+            Error::MissingEntry(..)               => return "missing_entry",
+            Error::UnclosedScope                  => return "unclosed_scope",
+            Error::ExpectedLocation               => return "expected_location",
+            Error::ExpectedLocationFound(..)      => return "expected_location_found",
+            Error::ExpectedImmediate              => return "expected_immediate",
+            Error::UnexpectedImmediate(..)        => return "unexpected_immediate",
+            Error::InvalidCompilerFunction(..)    => return "invalid_compiler_function",
+            Error::MissingFile(..)                => return "missing_file",
+            Error::UnterminatedToken(..)          => return "unterminated_token",
+            Error::ExpectedBooleanFound(..)       => return "expected_boolean_found",
+            Error::IndexOutOfBounds(..)           => return "index_out_of_bounds",
+            Error::InvalidPrefix(..)              => return "invalid_prefix",
+            Error::InvalidSuffix(..)              => return "invalid_suffix",
+            Error::InvalidNumber(..)              => return "invalid_number",
+            Error::ExpectedWord                   => return "expected_word",
+            Error::ExpectedWordFound(..)          => return "expected_word_found",
+            Error::InvalidNumberSystem(..)        => return "invalid_number_system",
+            Error::AmbiguousIdentifier(..)        => return "ambiguous_identifier",
+            Error::InconsistentIndentation(..)    => return "inconsistent_indentation",
+            Error::IntegerOverflow(..)            => return "integer_overflow",
+            Error::FloatOverflow(..)              => return "float_overflow",
         }
     }
 }
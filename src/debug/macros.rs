@@ -137,6 +137,8 @@ macro_rules! format_hook {
 #[macro_export]
 macro_rules! error {
     (Tag, $error:expr, $tag:expr)                                       => (Status::Error(Error::Tag($tag, Box::new($error))));
+    (Positioned, $error:expr, $positions:expr)                          => (Status::Error(Error::Positioned($positions, Box::new($error))));
+    (Reported, $error:expr)                                             => (Status::Error(Error::Reported(Box::new($error))));
     (Compiler, $errors:expr)                                            => (Status::Error(Error::Compiler($errors)));
     (Tokenizer, $errors:expr)                                           => (Status::Error(Error::Tokenizer($errors)));
     (Parser, $errors:expr)                                              => (Status::Error(Error::Parser($errors)));
@@ -192,6 +194,9 @@ macro_rules! error {
     (ExpectedWordFound, $found:expr)                                    => (Status::Error(Error::ExpectedWordFound($found)));
     (InvalidNumberSystem, $system:expr)                                 => (Status::Error(Error::InvalidNumberSystem($system)));
     (AmbiguousIdentifier, $identifier:expr)                             => (Status::Error(Error::AmbiguousIdentifier($identifier)));
+    (InconsistentIndentation, $indentation:expr)                        => (Status::Error(Error::InconsistentIndentation($indentation)));
+    (IntegerOverflow, $literal:expr)                                    => (Status::Error(Error::IntegerOverflow($literal)));
+    (FloatOverflow, $literal:expr)                                      => (Status::Error(Error::FloatOverflow($literal)));
     (Message, $message:expr)                                            => (Status::Error(Error::Message($message)));
     ($message:expr)                                                     => (Status::Error(Error::Message($message)));
 }
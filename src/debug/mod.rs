@@ -1,12 +1,16 @@
 #[macro_use]
 mod macros;
 mod position;
+mod source_map;
+mod diagnostic;
 mod status;
 mod error;
 
 use internal::*;
 
 pub use self::position::Position;
+pub use self::source_map::{ SourceMap, SourceSession };
+pub use self::diagnostic::{ Diagnostic, Label };
 pub use self::status::Status;
 pub use self::error::Error;
 
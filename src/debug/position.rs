@@ -1,174 +1,121 @@
 use internal::*;
 use debug::*;
 
-#[derive(Clone, PartialEq, Eq)]
-enum PositionKey {
-    Some(Option<SharedString>, SharedString),
-    None,
-}
-
-impl Compare for PositionKey {
-
-    fn compare(&self, other: &Self) -> Relation {
-        if let PositionKey::Some(file, source) = self {
-            if let PositionKey::Some(other_file, other_source) = other {
-                if let Some(file) = file {
-                    if let Some(other_file) = other_file {
-                        match file.compare(other_file) {
-                            Relation::Equal => {},
-                            relation => return relation,
-                        }
-                    }
-                }
-                return source.compare(other_source);
-            }
-        }
-        panic!();
-    }
-}
+use std::collections::BTreeMap;
 
 #[derive(Debug, Clone, PartialEq)]
 pub struct Position {
-    pub file:       Option<SharedString>,
-    pub source:     SharedString,
-    pub index:      usize,
-    pub line:       usize,
-    pub character:  usize,
-    pub length:     usize,
+    pub file:       usize,
+    pub start:      usize,
+    pub end:        usize,
 }
 
 impl Position {
 
-    pub fn new(file: Option<SharedString>, source: SharedString, index: usize, line: usize, character: usize, length: usize) -> Position {
+    pub fn new(file: usize, start: usize, end: usize) -> Position {
         Self {
             file:       file,
-            source:     source,
-            index:      index,
-            line:       line,
-            character:  character,
-            length:     length,
+            start:      start,
+            end:        end,
         }
     }
 
+    pub fn length(&self) -> usize {
+        return self.end - self.start;
+    }
+
+    pub fn line(&self) -> usize {
+        return SourceMap::line_character(self.file, self.start).0;
+    }
+
+    pub fn character(&self) -> usize {
+        return SourceMap::line_character(self.file, self.start).1;
+    }
+
+    pub fn file_name(&self) -> Option<SharedString> {
+        return SourceMap::name(self.file);
+    }
+
     pub fn serialize(&self) -> Data {
+        let (line, character) = SourceMap::line_character(self.file, self.start);
         let mut map = Map::new();
-        let file = self.file.clone().map(|file| Data::String(file)).unwrap_or(identifier!("none"));
-        map.insert(identifier!("file"), file);
-        map.insert(identifier!("source"), string!(String, self.source.clone()));
-        map.insert(identifier!("index"), integer!(self.index as i64));
-        map.insert(identifier!("line"), integer!(self.line as i64));
-        map.insert(identifier!("character"), integer!(self.character as i64));
-        map.insert(identifier!("length"), integer!(self.length as i64));
+        map.insert(identifier!("file"), integer!(self.file as i64));
+        map.insert(identifier!("start"), integer!(self.start as i64));
+        map.insert(identifier!("end"), integer!(self.end as i64));
+        map.insert(identifier!("line"), integer!(line as i64));
+        map.insert(identifier!("character"), integer!(character as i64));
         return map!(map);
     }
 
     pub fn serialize_partial(&self) -> Data {
         let mut map = Map::new();
-        map.insert(identifier!("index"), integer!(self.index as i64));
-        map.insert(identifier!("line"), integer!(self.line as i64));
-        map.insert(identifier!("character"), integer!(self.character as i64));
-        map.insert(identifier!("length"), integer!(self.length as i64));
+        map.insert(identifier!("start"), integer!(self.start as i64));
+        map.insert(identifier!("end"), integer!(self.end as i64));
         return map!(map);
     }
 
-    pub fn deserialize(serialized: &Data) -> Status<Self> {
-
-        let file = confirm!(serialized.index(&identifier!("file")));
-        let file = expect!(file, string!("position may not miss the file field"));
-        let file = (file != identifier!("none")).then_some(unpack_string!(&file));
-
-        let source = confirm!(serialized.index(&identifier!("source")));
-        let source = expect!(source, string!("position may not miss the source field"));
-        let source = unpack_string!(&source);
-
-        let index = confirm!(serialized.index(&identifier!("index")));
-        let index = expect!(index, string!("position may not miss the index field"));
-        let index = unpack_integer!(&index) as usize;
-
-        let line = confirm!(serialized.index(&identifier!("line")));
-        let line = expect!(line, string!("position may not miss the line field"));
-        let line = unpack_integer!(&line) as usize;
+    fn deserialize_span(serialized: &Data, file: usize) -> Status<Self> {
 
-        let character = confirm!(serialized.index(&identifier!("character")));
-        let character = expect!(character, string!("position may not miss the character field"));
-        let character = unpack_integer!(&character) as usize;
+        let start = confirm!(serialized.index(&identifier!("start")));
+        let start = expect!(start, string!("position may not miss the start field"));
+        let start = unpack_integer!(&start) as usize;
 
-        let length = confirm!(serialized.index(&identifier!("length")));
-        let length = expect!(length, string!("position may not miss the length field"));
-        let length = unpack_integer!(&length) as usize;
+        let end = confirm!(serialized.index(&identifier!("end")));
+        let end = expect!(end, string!("position may not miss the end field"));
+        let end = unpack_integer!(&end) as usize;
 
-        return success!(Self::new(file, source, index, line, character, length));
+        ensure!(start <= end, string!("position may not end before it starts"));
+        ensure!(end <= SourceMap::length(file), string!("position is outside of its source"));
+        return success!(Self::new(file, start, end));
     }
 
-    pub fn deserialize_partial(serialized: &Data, file: &Option<SharedString>, source: &SharedString) -> Status<Self> {
-
-        let index = confirm!(serialized.index(&identifier!("index")));
-        let index = expect!(index, string!("position may not miss the index field"));
-        let index = unpack_integer!(&index) as usize;
-
-        let line = confirm!(serialized.index(&identifier!("line")));
-        let line = expect!(line, string!("position may not miss the line field"));
-        let line = unpack_integer!(&line) as usize;
+    pub fn deserialize(serialized: &Data) -> Status<Self> {
 
-        let character = confirm!(serialized.index(&identifier!("character")));
-        let character = expect!(character, string!("position may not miss the character field"));
-        let character = unpack_integer!(&character) as usize;
+        let file = confirm!(serialized.index(&identifier!("file")));
+        let file = expect!(file, string!("position may not miss the file field"));
+        let file = unpack_integer!(&file) as usize;
+        ensure!(SourceMap::contains(file), string!("position refers to unknown source {}", file));
 
-        let length = confirm!(serialized.index(&identifier!("length")));
-        let length = expect!(length, string!("position may not miss the length field"));
-        let length = unpack_integer!(&length) as usize;
+        return Position::deserialize_span(serialized, file);
+    }
 
-        return success!(Self::new(file.clone(), source.clone(), index, line, character, length));
+    pub fn deserialize_partial(serialized: &Data, file: usize) -> Status<Self> {
+        return Position::deserialize_span(serialized, file);
     }
 
-    fn insert_positions(positions_map: &mut Map<PositionKey, Vec<Position>>, positions: Vec<Position>) {
-        for iterator in positions.into_iter() {
-            let key = PositionKey::Some(iterator.file.clone(), iterator.source.clone());
-            if let Some(map_entry) = positions_map.get_mut(&key) {
-                if !map_entry.contains(&iterator) {
-                    map_entry.push(iterator);
-                }
-                continue;
+    fn group_positions(positions: Vec<Position>, internal: bool) -> Vec<Vec<Position>> {
+        if internal {
+            return vec![positions];
+        }
+
+        let mut positions_map: BTreeMap<usize, Vec<Position>> = BTreeMap::new();
+        for position in positions.into_iter() {
+            let map_entry = positions_map.entry(position.file).or_insert_with(Vec::new);
+            if !map_entry.contains(&position) {
+                map_entry.push(position);
             }
-            positions_map.insert(key, vec![iterator]);
         }
-    }
 
-    fn sort_positions(positions_map: &mut Map<PositionKey, Vec<Position>>) {
         // TODO: ENSURE length IS NOT 0!!!!!!!!!!!!!!!!!!!1
-
-        let sorter = |left: &Position, right: &Position| {
-            if left.line != right.line {
-                return left.line.cmp(&right.line);
-            };
-            return left.character.cmp(&right.character);
-        };
-
         for positions in positions_map.values_mut() {
-            positions.sort_by(sorter);
+            positions.sort_by(|left, right| left.start.cmp(&right.start));
         }
+
+        return positions_map.into_iter().map(|(_, positions)| positions).collect();
     }
 
     pub fn fuze(positions: Vec<Position>, internal: bool) -> Vec<Position> {
-        let mut positions_map = Map::new();
         let mut return_positions = Vec::new();
 
-        if internal {
-            positions_map.insert(PositionKey::None, positions);
-        } else {
-            Position::insert_positions(&mut positions_map, positions);
-            Position::sort_positions(&mut positions_map);
-        }
-
-        for mut positions in positions_map.values().cloned() {
+        for mut positions in Position::group_positions(positions, internal).into_iter() {
             if positions.is_empty() {
                 continue;
             }
 
             let mut offset = 0;
             while offset < positions.len() - 1 {
-                if positions[offset].line == positions[offset + 1].line && positions[offset].character + positions[offset].length == positions[offset + 1].character {
-                    positions[offset].length += positions[offset + 1].length;
+                if positions[offset].end == positions[offset + 1].start {
+                    positions[offset].end = positions[offset + 1].end;
                     positions.remove(offset + 1);
                 } else {
                     offset += 1;
@@ -182,70 +129,19 @@ impl Position {
     }
 
     pub fn range(positions: Vec<Position>, internal: bool) -> Vec<Position> {
-        let mut positions_map = Map::new();
         let mut return_positions = Vec::new();
 
-        if internal {
-            positions_map.insert(PositionKey::None, positions);
-        } else {
-            Position::insert_positions(&mut positions_map, positions);
-            Position::sort_positions(&mut positions_map);
-        }
-
-        for positions in positions_map.values() {
+        for positions in Position::group_positions(positions, internal).into_iter() {
             if positions.is_empty() {
                 continue;
             }
 
-            if positions.len() == 1 {
-                return_positions.push(positions[0].clone());
-                continue;
-            }
-
-            let first = positions[0].clone();
-            let last = positions.last().unwrap().clone();
-
-            let mut offset = 0;
-            let mut line = 1;
-            let mut character = 1;
-            while line != first.line || character != first.character + first.length {
-                match first.source[offset].as_char() {
-
-                    '\n' => {
-                        character = 1;
-                        line += 1;
-                    }
-
-                    _other => {
-                        character += 1;
-                    }
-                }
-                offset += 1;
-            }
-
-            return_positions.push(first);
-
-            while line != last.line || character != last.character {
-                match last.source[offset].as_char() {
-
-                    '\n' => {
-                        line += 1;
-                        character = 1;
-                        return_positions.push(Position::new(last.file.clone(), last.source.clone(), offset, line, 1, 0));
-                    }
-
-                    _other => {
-                        character += 1;
-                        return_positions.last_mut().unwrap().length += 1;
-                    }
-                }
-                offset += 1;
-            }
-
-            return_positions.last_mut().unwrap().length += last.length;
+            let first = positions.first().unwrap();
+            let last = positions.last().unwrap();
+            return_positions.push(Position::new(first.file, first.start, last.end));
         }
 
-        return_positions.retain(|position| position.length != 0);
+        return_positions.retain(|position| position.length() != 0);
         return return_positions;
     }
 }
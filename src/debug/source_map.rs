@@ -0,0 +1,216 @@
+use internal::*;
+
+use std::cell::RefCell;
+
+static STREAMED_LINES: usize = 64;
+static STREAMED_CHARACTERS: usize = 16384;
+
+struct SourceFile {
+    name:           Option<Vec<Character>>,
+    source:         Vec<Character>,
+    source_start:   usize,
+    line_starts:    Vec<usize>,
+    dropped_lines:  usize,
+    length:         usize,
+}
+
+impl SourceFile {
+
+    fn new(name: Option<Vec<Character>>, source: Vec<Character>) -> Self {
+        let mut line_starts = vec![0];
+        for (index, character) in source.iter().enumerate() {
+            if character.is_newline() {
+                line_starts.push(index + 1);
+            }
+        }
+
+        Self {
+            name:           name,
+            length:         source.len(),
+            source:         source,
+            source_start:   0,
+            line_starts:    line_starts,
+            dropped_lines:  0,
+        }
+    }
+
+    // streamed files only keep the line starts and text of the most recent lines, so snippets of the current token can still be shown
+    // the text of a single overlong line is cut down to its end as well
+    fn new_stream(name: Option<Vec<Character>>) -> Self {
+        Self {
+            name:           name,
+            source:         Vec::new(),
+            source_start:   0,
+            line_starts:    vec![0],
+            dropped_lines:  0,
+            length:         0,
+        }
+    }
+
+    fn extend(&mut self, characters: &[Character]) {
+        for (index, character) in characters.iter().enumerate() {
+            if character.is_newline() {
+                self.line_starts.push(self.length + index + 1);
+            }
+        }
+        self.length += characters.len();
+        self.source.extend_from_slice(characters);
+
+        if self.line_starts.len() > STREAMED_LINES {
+            let dropped = self.line_starts.len() - STREAMED_LINES;
+            self.line_starts.drain(..dropped);
+            self.dropped_lines += dropped;
+        }
+
+        let kept_start = self.line_starts[0].max(self.length.saturating_sub(STREAMED_CHARACTERS));
+        if kept_start > self.source_start {
+            self.source.drain(..kept_start - self.source_start);
+            self.source_start = kept_start;
+        }
+    }
+
+    // lines of streamed files that were already dropped are empty, an overlong line only shows its kept end
+    fn line(&self, line: usize) -> SharedString {
+        let start = match line.checked_sub(self.dropped_lines + 1).and_then(|line| self.line_starts.get(line)) {
+            Some(start) => start.saturating_sub(self.source_start),
+            None => return SharedString::new(),
+        };
+
+        let mut end = match self.line_starts.get(line - self.dropped_lines) {
+            Some(next_start) => next_start.saturating_sub(self.source_start),
+            None => self.source.len(),
+        };
+
+        while end > start && (self.source[end - 1].is_newline() || self.source[end - 1].as_unicode() == '\r') {
+            end -= 1;
+        }
+        return self.source[start..end].iter().cloned().collect();
+    }
+
+    // number of characters at the start of an overlong line whose text was already dropped
+    fn line_cut(&self, line: usize) -> usize {
+        match line.checked_sub(self.dropped_lines + 1).and_then(|line| self.line_starts.get(line)) {
+            Some(start) => return self.source_start.saturating_sub(*start),
+            None => return 0,
+        }
+    }
+
+    // indices on lines that were already dropped have no line and character
+    fn line_character(&self, index: usize) -> (usize, usize) {
+        let line = match self.line_starts.binary_search(&index) {
+            Ok(line) => line,
+            Err(0) => return (0, 0),
+            Err(line) => line - 1,
+        };
+        return (self.dropped_lines + line + 1, index - self.line_starts[line] + 1);
+    }
+}
+
+// files are only kept while a session is open, the outermost session drops them once it ends
+// ids keep counting from the dropped files, so a stale position never refers to a newer file
+struct Sessions {
+    files:          Vec<SourceFile>,
+    first:          usize,
+    depth:          usize,
+}
+
+thread_local! {
+    static SOURCE_MAP: RefCell<Sessions> = RefCell::new(Sessions { files: Vec::new(), first: 0, depth: 0 });
+}
+
+pub struct SourceSession;
+
+impl Drop for SourceSession {
+
+    fn drop(&mut self) {
+        SOURCE_MAP.with(|source_map| {
+            let mut source_map = source_map.borrow_mut();
+            source_map.depth -= 1;
+            if source_map.depth == 0 {
+                source_map.first += source_map.files.len();
+                source_map.files.clear();
+            }
+        });
+    }
+}
+
+pub struct SourceMap;
+
+impl SourceMap {
+
+    pub const UNREGISTERED: usize = usize::max_value();
+
+    // functions, builds, tokenizing and parsing open a session, file ids are only valid until the outermost one ends
+    pub fn session() -> SourceSession {
+        SOURCE_MAP.with(|source_map| source_map.borrow_mut().depth += 1);
+        return SourceSession;
+    }
+
+    fn with_file<T, F: FnOnce(&SourceFile) -> T>(file: usize, default: T, callback: F) -> T {
+        return SOURCE_MAP.with(|source_map| {
+            let source_map = source_map.borrow();
+            match file.checked_sub(source_map.first).and_then(|index| source_map.files.get(index)) {
+                Some(source_file) => callback(source_file),
+                None => default,
+            }
+        });
+    }
+
+    pub fn register(name: &Option<SharedString>, source: &SharedString) -> usize {
+        let name: Option<Vec<Character>> = name.as_ref().map(|name| name.chars().cloned().collect());
+        let source: Vec<Character> = source.chars().cloned().collect();
+        return SOURCE_MAP.with(|source_map| {
+            let mut source_map = source_map.borrow_mut();
+            source_map.files.push(SourceFile::new(name, source));
+            source_map.first + source_map.files.len() - 1
+        });
+    }
+
+    pub fn register_stream(name: &Option<SharedString>) -> usize {
+        let name: Option<Vec<Character>> = name.as_ref().map(|name| name.chars().cloned().collect());
+        return SOURCE_MAP.with(|source_map| {
+            let mut source_map = source_map.borrow_mut();
+            source_map.files.push(SourceFile::new_stream(name));
+            source_map.first + source_map.files.len() - 1
+        });
+    }
+
+    pub fn extend_stream(file: usize, characters: &[Character]) {
+        SOURCE_MAP.with(|source_map| {
+            let mut source_map = source_map.borrow_mut();
+            let index = file.wrapping_sub(source_map.first);
+            if let Some(source_file) = source_map.files.get_mut(index) {
+                source_file.extend(characters);
+            }
+        });
+    }
+
+    pub fn contains(file: usize) -> bool {
+        return SourceMap::with_file(file, false, |_| true);
+    }
+
+    pub fn name(file: usize) -> Option<SharedString> {
+        return SourceMap::with_file(file, None, |source_file| source_file.name.as_ref().map(|name| name.iter().cloned().collect()));
+    }
+
+    pub fn source(file: usize) -> SharedString {
+        return SourceMap::with_file(file, SharedString::new(), |source_file| source_file.source.iter().cloned().collect());
+    }
+
+    pub fn length(file: usize) -> usize {
+        return SourceMap::with_file(file, 0, |source_file| source_file.length);
+    }
+
+    pub fn line(file: usize, line: usize) -> SharedString {
+        return SourceMap::with_file(file, SharedString::new(), |source_file| source_file.line(line));
+    }
+
+    pub fn line_cut(file: usize, line: usize) -> usize {
+        return SourceMap::with_file(file, 0, |source_file| source_file.line_cut(line));
+    }
+
+    // positions of files that are not registered in the current session have no line and character
+    pub fn line_character(file: usize, index: usize) -> (usize, usize) {
+        return SourceMap::with_file(file, (0, 0), |source_file| source_file.line_character(index));
+    }
+}
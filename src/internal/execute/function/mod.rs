@@ -8,6 +8,9 @@ use self::parameter::FunctionParameter;
 
 pub fn function(function_path: &Data, parameters: SharedVector<Data>, pass: &Option<Pass>, root: &Data, build: &Data) -> Status<Option<Data>> {
 
+    // positions returned by #tokenize and #parse stay valid until the outermost function returns
+    let _session = SourceMap::session();
+
     let mut full_steps = vector![keyword!("functions")];
     if function_path.is_path() {
         unpack_path!(function_path).iter().for_each(|step| full_steps.push(step.clone()));
@@ -101,18 +101,32 @@ lazy_static! {
         push_description!(map, "keys",          Keys,           true,   false,  false,  InstructionParameter::new(Some(vec![ParameterType::Map])));
         push_description!(map, "values",        Values,         true,   false,  false,  InstructionParameter::new(Some(vec![ParameterType::Container])));
         push_description!(map, "pairs",         Pairs,          true,   false,  false,  InstructionParameter::new(Some(vec![ParameterType::Container])));
-        push_description!(map, "serialize",     Serialize,      true,   false,  false,  InstructionParameter::new(None));
+        push_description!(map, "serialize",     Serialize,      true,   false,  true,   InstructionParameter::new(None), InstructionParameter::new(Some(vec![ParameterType::Map])));
         push_description!(map, "deserialize",   Deserialize,    true,   false,  false,  InstructionParameter::new(Some(vec![ParameterType::String])));
         push_description!(map, "read_file",     ReadFile,       true,   false,  false,  InstructionParameter::new(Some(vec![ParameterType::String])));
         push_description!(map, "write_file",    WriteFile,      true,   false,  false,  InstructionParameter::new(Some(vec![ParameterType::String])), InstructionParameter::new(Some(vec![ParameterType::String])));
         push_description!(map, "read_map",      ReadMap,        true,   false,  false,  InstructionParameter::new(Some(vec![ParameterType::String])));
-        push_description!(map, "write_map",     WriteMap,       true,   false,  false,  InstructionParameter::new(Some(vec![ParameterType::String])), InstructionParameter::new(Some(vec![ParameterType::Map])));
+        push_description!(map, "write_map",     WriteMap,       true,   false,  true,   InstructionParameter::new(Some(vec![ParameterType::String])), InstructionParameter::new(Some(vec![ParameterType::Map])), InstructionParameter::new(Some(vec![ParameterType::Map])));
         push_description!(map, "read_list",     ReadList,       true,   false,  false,  InstructionParameter::new(Some(vec![ParameterType::String])));
-        push_description!(map, "write_list",    WriteList,      true,   false,  false,  InstructionParameter::new(Some(vec![ParameterType::String])), InstructionParameter::new(Some(vec![ParameterType::List])));
+        push_description!(map, "write_list",    WriteList,      true,   false,  true,   InstructionParameter::new(Some(vec![ParameterType::String])), InstructionParameter::new(Some(vec![ParameterType::List])), InstructionParameter::new(Some(vec![ParameterType::Map])));
+        push_description!(map, "read_json",     ReadJson,       true,   false,  false,  InstructionParameter::new(Some(vec![ParameterType::String])), InstructionParameter::new(Some(vec![ParameterType::Boolean])));
+        push_description!(map, "write_json",    WriteJson,      true,   false,  false,  InstructionParameter::new(Some(vec![ParameterType::String])), InstructionParameter::new(None), InstructionParameter::new(Some(vec![ParameterType::Boolean])));
+        push_description!(map, "to_json",       ToJson,         true,   false,  false,  InstructionParameter::new(None), InstructionParameter::new(Some(vec![ParameterType::Boolean])));
+        push_description!(map, "from_json",     FromJson,       true,   false,  false,  InstructionParameter::new(Some(vec![ParameterType::String])), InstructionParameter::new(Some(vec![ParameterType::Boolean])));
+        push_description!(map, "read_binary",   ReadBinary,     true,   false,  false,  InstructionParameter::new(Some(vec![ParameterType::String])));
+        push_description!(map, "write_binary",  WriteBinary,    true,   false,  false,  InstructionParameter::new(Some(vec![ParameterType::String])), InstructionParameter::new(None));
+        push_description!(map, "edit_entry",    EditEntry,      true,   false,  false,  InstructionParameter::new(Some(vec![ParameterType::String])), InstructionParameter::new(Some(vec![ParameterType::Selector, ParameterType::Path])), InstructionParameter::new(None));
+        push_description!(map, "remove_entry",  RemoveEntry,    true,   false,  false,  InstructionParameter::new(Some(vec![ParameterType::String])), InstructionParameter::new(Some(vec![ParameterType::Selector, ParameterType::Path])));
+        push_description!(map, "comments",      Comments,       true,   false,  false,  InstructionParameter::new(Some(vec![ParameterType::String])), InstructionParameter::new(Some(vec![ParameterType::Selector, ParameterType::Path])));
+        push_description!(map, "library",       Library,        true,   false,  false,  InstructionParameter::new(Some(vec![ParameterType::Literal])));
+        push_description!(map, "report",        Report,         true,   false,  false,  InstructionParameter::new(Some(vec![ParameterType::Map])));
+        push_description!(map, "warning",       Warning,        true,   false,  true,   InstructionParameter::new(Some(vec![ParameterType::Identifier])), InstructionParameter::new(Some(vec![ParameterType::String])), InstructionParameter::new(Some(vec![ParameterType::Map, ParameterType::List])));
+        push_description!(map, "note",          Note,           true,   false,  true,   InstructionParameter::new(Some(vec![ParameterType::Identifier])), InstructionParameter::new(Some(vec![ParameterType::String])), InstructionParameter::new(Some(vec![ParameterType::Map, ParameterType::List])));
         push_description!(map, "modify",        Modify,         true,   false,  true,   InstructionParameter::new(Some(vec![ParameterType::Key, ParameterType::Path])), InstructionParameter::new(None), InstructionParameter::new(None));
         push_description!(map, "call",          Call,           true,   false,  true,   InstructionParameter::new(Some(vec![ParameterType::Key, ParameterType::Path])), InstructionParameter::new(None));
         push_description!(map, "call_list",     CallList,       true,   false,  true,   InstructionParameter::new(Some(vec![ParameterType::Key, ParameterType::Path])), InstructionParameter::new(Some(vec![ParameterType::List])));
         push_description!(map, "invoke",        Invoke,         true,   false,  false,  InstructionParameter::new(Some(vec![ParameterType::Keyword])), InstructionParameter::new(Some(vec![ParameterType::List])));
+        push_description!(map, "attempt",       Attempt,        true,   false,  false,  InstructionParameter::new(Some(vec![ParameterType::Keyword])), InstructionParameter::new(Some(vec![ParameterType::List])));
         push_description!(map, "pass",          Pass,           true,   false,  true,   InstructionParameter::new(None), InstructionParameter::new(None));
         push_description!(map, "new_pass",      NewPass,        true,   false,  true,   InstructionParameter::new(Some(vec![ParameterType::Literal])), InstructionParameter::new(None), InstructionParameter::new(None));
         push_description!(map, "merge",         Merge,          true,   false,  true,   InstructionParameter::new(Some(vec![ParameterType::Container])), InstructionParameter::new(Some(vec![ParameterType::Container])), InstructionParameter::new(Some(vec![ParameterType::Container])));
@@ -63,6 +63,14 @@ macro_rules! combine_data {
     });
 }
 
+fn pretty_options(parameters: &Vec<Data>, index: usize) -> Status<Option<PrettyOptions>> {
+    ensure!(parameters.len() <= index + 1, UnexpectedParameter, parameters[index + 1].clone());
+    match parameters.get(index) {
+        Some(options) => return success!(Some(confirm!(PrettyOptions::deserialize(options)))),
+        None => return success!(None),
+    }
+}
+
 pub fn instruction(name: &SharedString, raw_parameters: Option<SharedVector<Data>>, stack: &mut DataStack, last: &mut Option<Data>, pass: &Option<Pass>, root: &Data, scope: &Data, build: &Data) -> Status<bool> {
     let internal_name = name.printable();
     let description = match (*INSTRUCTIONS).get(internal_name.as_str()) {
@@ -374,7 +382,12 @@ pub fn instruction(name: &SharedString, raw_parameters: Option<SharedVector<Data
                 *last = None;
             }
 
-            Signature::Serialize => *last = Some(string!(String, parameters[0].serialize())),
+            Signature::Serialize => {
+                match confirm!(pretty_options(&parameters, 1)) {
+                    Some(options) => *last = Some(string!(String, parameters[0].serialize_pretty(&options, 0))),
+                    None => *last = Some(string!(String, parameters[0].serialize())),
+                }
+            }
 
             Signature::Deserialize => {
                 let source = unpack_string!(&parameters[0]);
@@ -412,6 +425,40 @@ pub fn instruction(name: &SharedString, raw_parameters: Option<SharedVector<Data
                 }
             },
 
+            // errors of the invoked instruction are returned as data instead of aborting
+            Signature::Attempt => {
+                let passed_parameters = match parameters.len() {
+                    1 => SharedVector::new(),
+                    2 => unpack_list!(&parameters[1]),
+                    _ => return error!(UnexpectedParameter, parameters[2].clone()),
+                };
+                let instruction_name = unpack_keyword!(&parameters[0]);
+
+                let mut attempted = None;
+                let mut return_map = DataMap::new();
+                let returned = match instruction(&instruction_name, Some(passed_parameters), stack, &mut attempted, pass, root, scope, build) {
+
+                    Status::Success(returned) => {
+                        return_map.insert(identifier!("success"), boolean!(true));
+                        if let Some(value) = attempted {
+                            return_map.insert(identifier!("value"), value);
+                        }
+                        returned
+                    },
+
+                    Status::Error(error) => {
+                        return_map.insert(identifier!("success"), boolean!(false));
+                        return_map.insert(identifier!("error"), string!(String, error.display(&Some(root), build)));
+                        false
+                    },
+                };
+
+                *last = Some(map!(return_map));
+                if returned {
+                    return success!(true);
+                }
+            },
+
             Signature::Resolve => {
                 match &parameters[0] {
 
@@ -503,7 +550,8 @@ pub fn instruction(name: &SharedString, raw_parameters: Option<SharedVector<Data
 
             Signature::WriteMap => {
                 let filename = unpack_string!(&parameters[0]);
-                confirm!(write_map(&filename, &parameters[1]));
+                let options = confirm!(pretty_options(&parameters, 2));
+                confirm!(write_map(&filename, &parameters[1], options.as_ref()));
                 *last = None;
             }
 
@@ -511,7 +559,96 @@ pub fn instruction(name: &SharedString, raw_parameters: Option<SharedVector<Data
 
             Signature::WriteList => {
                 let filename = unpack_string!(&parameters[0]);
-                confirm!(write_list(&filename, &parameters[1]));
+                let options = confirm!(pretty_options(&parameters, 2));
+                confirm!(write_list(&filename, &parameters[1], options.as_ref()));
+                *last = None;
+            }
+
+            Signature::ReadJson => *last = Some(confirm!(read_json(&unpack_string!(&parameters[0]), unpack_boolean!(&parameters[1])))),
+
+            Signature::WriteJson => {
+                let filename = unpack_string!(&parameters[0]);
+                confirm!(write_json(&filename, &parameters[1], unpack_boolean!(&parameters[2])));
+                *last = None;
+            }
+
+            Signature::ToJson => *last = Some(string!(String, confirm!(parameters[0].to_json(unpack_boolean!(&parameters[1]))))),
+
+            Signature::FromJson => *last = Some(confirm!(Data::from_json(&unpack_string!(&parameters[0]), unpack_boolean!(&parameters[1])))),
+
+            Signature::ReadBinary => *last = Some(confirm!(read_binary(&unpack_string!(&parameters[0])))),
+
+            Signature::WriteBinary => {
+                let filename = unpack_string!(&parameters[0]);
+                confirm!(write_binary(&filename, &parameters[1]));
+                *last = None;
+            }
+
+            Signature::EditEntry => {
+                let mut document = confirm!(Document::parse(unpack_string!(&parameters[0])));
+                confirm!(document.set(&parameters[1], &parameters[2]));
+                *last = Some(string!(String, document.source()));
+            }
+
+            Signature::RemoveEntry => {
+                let mut document = confirm!(Document::parse(unpack_string!(&parameters[0])));
+                confirm!(document.remove(&parameters[1]));
+                *last = Some(string!(String, document.source()));
+            }
+
+            Signature::Comments => {
+                let document = confirm!(Document::parse(unpack_string!(&parameters[0])));
+                let comments = confirm!(document.comments(&parameters[1]));
+                *last = Some(list!(comments.into_iter().map(|comment| string!(String, comment)).collect()));
+            }
+
+            Signature::Library => *last = Some(confirm!(read_library(&unpack_literal!(&parameters[0])))),
+
+            Signature::Report => {
+                let _session = SourceMap::session();
+                let diagnostic = confirm!(Diagnostic::deserialize(&parameters[0]));
+                let color = match confirm!(parameters[0].index(&identifier!("color"))) {
+                    Some(color) => unpack_boolean!(&color),
+                    None => false,
+                };
+                let json = match confirm!(parameters[0].index(&identifier!("format"))) {
+                    Some(format) => match unpack_identifier!(&format).printable().as_str() {
+                        "json" => true,
+                        "text" => false,
+                        _other => return error!(string!("report format must be either text or json")),
+                    },
+                    None => false,
+                };
+
+                match json {
+                    true => *last = Some(string!(String, confirm!(diagnostic.to_json()))),
+                    false => *last = Some(string!(String, diagnostic.render(color))),
+                }
+            }
+
+            Signature::Warning | Signature::Note => {
+                let severity = match &description.signature {
+                    Signature::Warning => "warning",
+                    _other => "note",
+                };
+
+                let mut diagnostic = Diagnostic::new(severity, unpack_string!(&parameters[1]));
+                diagnostic.kind = unpack_identifier!(&parameters[0]);
+
+                for (index, positions) in parameters.iter().skip(2).enumerate() {
+                    let positions = match positions {
+                        Data::List(positions) => positions.clone(),
+                        position => vector![position.clone()],
+                    };
+
+                    let mut deserialized = Vec::new();
+                    for position in positions.iter() {
+                        deserialized.push(confirm!(Position::deserialize(position)));
+                    }
+                    diagnostic.add_label(&deserialized, None, index == 0);
+                }
+
+                confirm!(diagnostic.record(root, build));
                 *last = None;
             }
 
@@ -598,10 +735,10 @@ pub fn instruction(name: &SharedString, raw_parameters: Option<SharedVector<Data
             Signature::Tokenize => *last = Some(confirm!(call_tokenize(&parameters[0], &parameters[1], &parameters[2], &parameters[3], &parameters[4], build))),
 
             #[cfg(feature = "parse")]
-            Signature::Parse => *last = Some(confirm!(call_parse(&parameters[0], &parameters[1], &parameters[2]))),
+            Signature::Parse => *last = Some(confirm!(call_parse(&parameters[0], &parameters[1], &parameters[2], build))),
 
             #[cfg(feature = "build")]
-            Signature::Build => *last = Some(confirm!(call_build(&parameters[0], &parameters[1]))),
+            Signature::Build => *last = Some(confirm!(call_build(&parameters[0], &parameters[1], build))),
 
             _invalid => panic!(),
         }
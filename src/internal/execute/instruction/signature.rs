@@ -76,10 +76,24 @@ pub enum Signature {
     WriteMap,
     ReadList,
     WriteList,
+    ReadJson,
+    WriteJson,
+    ToJson,
+    FromJson,
+    ReadBinary,
+    WriteBinary,
+    EditEntry,
+    RemoveEntry,
+    Comments,
+    Library,
+    Report,
+    Warning,
+    Note,
     Modify,
     Call,
     CallList,
     Invoke,
+    Attempt,
     Pass,
     NewPass,
     Merge,
@@ -45,12 +45,37 @@ pub fn write_file(path: &SharedString, string: &SharedString) -> Status<()> {
     return write_file_raw(path, &string.printable());
 }
 
-pub fn write_map(path: &SharedString, instance: &Data) -> Status<()> {
+fn read_document(path: &SharedString) -> Status<Option<Document>> {
+    match read_file_raw(path) {
+        Status::Success(string) => return success!(Some(confirm!(Document::parse(SharedString::from(&string))))),
+        Status::Error(..) => return success!(None),
+    }
+}
+
+pub fn write_map(path: &SharedString, instance: &Data, options: Option<&PrettyOptions>) -> Status<()> {
     match instance {
         Data::Map(map) => {
+            let document = match options {
+                Some(options) if options.comments => confirm!(read_document(path)),
+                _other => None,
+            };
+
             let mut string = String::new();
             for (key, instance) in map.iter() {
-                string.push_str(&format!("{} {}\n", key.serialize(), instance.serialize()));
+                if let Some(document) = &document {
+                    if confirm!(document.contains(&key)) {
+                        for line in confirm!(document.comments(&key)).iter() {
+                            string.push_str(&format!("@ {}\n", line.unicode()));
+                        }
+                    }
+                }
+
+                let key = key.serialize();
+                let instance = match options {
+                    Some(options) => instance.serialize_pretty(options, key.len() + 1).printable(),
+                    None => instance.serialize().printable(),
+                };
+                string.push_str(&format!("{} {}\n", key, instance));
             }
             return write_file_raw(path, &string);
         }
@@ -58,12 +83,16 @@ pub fn write_map(path: &SharedString, instance: &Data) -> Status<()> {
     }
 }
 
-pub fn write_list(path: &SharedString, instance: &Data) -> Status<()> {
+pub fn write_list(path: &SharedString, instance: &Data, options: Option<&PrettyOptions>) -> Status<()> {
     match instance {
         Data::List(items) => {
             let mut string = String::new();
             for instance in items.iter() {
-                string.push_str(&format!("{}\n", instance.serialize()));
+                let instance = match options {
+                    Some(options) => instance.serialize_pretty(options, 0).printable(),
+                    None => instance.serialize().printable(),
+                };
+                string.push_str(&format!("{}\n", instance));
             }
             return write_file_raw(path, &string);
         }
@@ -71,6 +100,37 @@ pub fn write_list(path: &SharedString, instance: &Data) -> Status<()> {
     }
 }
 
+pub fn read_json(path: &SharedString, tagged: bool) -> Status<Data> {
+    return Data::from_json(&confirm!(read_file(path)), tagged);
+}
+
+pub fn write_json(path: &SharedString, instance: &Data, tagged: bool) -> Status<()> {
+    return write_file_raw(path, &confirm!(instance.to_json(tagged)).unicode());
+}
+
+pub fn read_binary(path: &SharedString) -> Status<Data> {
+    let mut bytes = Vec::new();
+    let mut file = match File::open(path.printable()) {
+        Err(..) => return error!(string!("missing file \"{}\"", path)),
+        Ok(file) => file,
+    };
+    if let Err(..) = file.read_to_end(&mut bytes) {
+        return error!(string!("failed to read file \"{}\"", path));
+    }
+    return Data::from_binary(&bytes);
+}
+
+pub fn write_binary(path: &SharedString, instance: &Data) -> Status<()> {
+    let mut file = match File::create(&path.printable()) {
+        Err(..) => return error!(string!("failed to create file \"{}\"", path)),
+        Ok(file) => file,
+    };
+    if let Err(..) = file.write_all(&instance.to_binary()) {
+        return error!(string!("failed to write file \"{}\"", path));
+    }
+    return success!(());
+}
+
 pub fn get_directory_entries(path: &SharedString) -> Status<Vec<SharedString>> {
 
     let paths = match read_dir(path.serialize()) {
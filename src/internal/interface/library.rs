@@ -0,0 +1,40 @@
+use internal::*;
+use debug::*;
+
+pub const LIBRARY_VERSION: i64 = 1;
+
+macro_rules! pack {
+    ($name:expr) => (
+        ($name, include_str!(concat!("../../../library/", $name)))
+    );
+}
+
+static PACKS: [(&str, &str); 6] = [
+    pack!("c_tokens"),
+    pack!("c_comments"),
+    pack!("shell_comments"),
+    pack!("json"),
+    pack!("ini"),
+    pack!("arithmetic"),
+];
+
+pub fn library_packs() -> Vec<SharedString> {
+    return PACKS.iter().map(|(name, _)| SharedString::from(name)).collect();
+}
+
+pub fn read_library(name: &SharedString) -> Status<Data> {
+    let printable = name.printable();
+    let source = match PACKS.iter().find(|(pack, _)| *pack == printable.as_str()) {
+        Some((_, source)) => source,
+        None => return error!(string!("missing library pack \"{}\"", name)),
+    };
+
+    let mut character_stack = CharacterStack::new(format_shared!("{{{}}}", source), None);
+    let pack = confirm!(parse_data(&mut character_stack));
+    let version = confirm!(pack.index(&identifier!("version")));
+    let version = expect!(version, string!("library pack \"{}\" is missing a version", name));
+    ensure!(version == integer!(LIBRARY_VERSION), string!("library pack \"{}\" has version {} but {} is required", name, version.serialize(), LIBRARY_VERSION));
+
+    // the version only describes the pack, merging it into a compiler would add an unknown setting
+    return pack.remove(&identifier!("version"));
+}
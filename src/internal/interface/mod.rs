@@ -1,3 +1,5 @@
 mod file;
+mod library;
 
 pub use self::file::*;
+pub use self::library::*;
@@ -0,0 +1,287 @@
+use internal::*;
+use debug::*;
+
+use super::{ update, update_comments };
+
+#[derive(Debug, Clone)]
+pub struct DocumentNode {
+    pub start:      usize,
+    pub end:        usize,
+    pub entries:    Vec<DocumentEntry>,
+}
+
+#[derive(Debug, Clone)]
+pub struct DocumentEntry {
+    pub leading:    usize,
+    pub start:      usize,
+    pub key:        Option<Data>,
+    pub value:      DocumentNode,
+}
+
+fn substring(source: &SharedString, start: usize, end: usize) -> SharedString {
+    return source.chars().skip(start).take(end - start).cloned().collect();
+}
+
+fn index(character_stack: &CharacterStack) -> usize {
+    return character_stack.current_position().start;
+}
+
+// trivia on the same line as the previous entry stays with the previous entry, the first entry owns everything in front of it
+fn leading_start(source: &SharedString, previous_end: usize, start: usize) -> usize {
+    for index in previous_end..start {
+        if source[index].is_newline() {
+            return index + 1;
+        }
+    }
+    return previous_end;
+}
+
+fn parse_entries(character_stack: &mut CharacterStack, source: &SharedString, start: usize, closing: Option<char>, keyed: bool) -> Status<Vec<DocumentEntry>> {
+    let mut entries = Vec::new();
+    let mut previous_end = start;
+
+    loop {
+        confirm!(update(character_stack));
+        if let Some(closing) = closing {
+            if character_stack.peek(0).map(|character| character.as_char() == closing).unwrap_or(false) {
+                return success!(entries);
+            }
+            ensure!(!character_stack.is_empty(), UnterminatedToken, identifier!(if keyed { "map" } else { "list" }));
+        } else if character_stack.is_empty() {
+            return success!(entries);
+        }
+
+        let entry_start = index(character_stack);
+        let key = match keyed {
+            true => Some(confirm!(parse_data(character_stack))),
+            false => None,
+        };
+
+        let value = confirm!(parse_node(character_stack, source));
+        let leading = match entries.is_empty() {
+            true => previous_end,
+            false => leading_start(source, previous_end, entry_start),
+        };
+        previous_end = value.end;
+
+        entries.push(DocumentEntry {
+            leading:    leading,
+            start:      entry_start,
+            key:        key,
+            value:      value,
+        });
+    }
+}
+
+fn parse_node(character_stack: &mut CharacterStack, source: &SharedString) -> Status<DocumentNode> {
+    confirm!(update(character_stack));
+    let start = index(character_stack);
+
+    let entries = match character_stack.peek(0).map(|character| character.as_char()) {
+
+        Some('{') => {
+            character_stack.advance(1);
+            let entries = confirm!(parse_entries(character_stack, source, start + 1, Some('}'), true));
+            character_stack.advance(1);
+            entries
+        }
+
+        Some('[') => {
+            character_stack.advance(1);
+            let entries = confirm!(parse_entries(character_stack, source, start + 1, Some(']'), false));
+            character_stack.advance(1);
+            entries
+        }
+
+        _other => {
+            confirm!(parse_data(character_stack));
+            Vec::new()
+        }
+    };
+
+    return success!(DocumentNode {
+        start:      start,
+        end:        index(character_stack),
+        entries:    entries,
+    });
+}
+
+// a definition file that keeps every comment and blank line, edits only replace the text they touch
+#[derive(Debug, Clone)]
+pub struct Document {
+    source:         SharedString,
+    root:           DocumentNode,
+}
+
+impl Document {
+
+    pub fn parse(source: SharedString) -> Status<Self> {
+        let mut character_stack = CharacterStack::new(source.clone(), None);
+        let entries = confirm!(parse_entries(&mut character_stack, &source, 0, None, true));
+
+        let root = DocumentNode {
+            start:      0,
+            end:        source.len(),
+            entries:    entries,
+        };
+
+        return success!(Self {
+            source:     source,
+            root:       root,
+        });
+    }
+
+    pub fn source(&self) -> SharedString {
+        return self.source.clone();
+    }
+
+    pub fn data(&self) -> Status<Data> {
+        let mut wrapped = SharedString::from("{");
+        wrapped.push_str(&self.source);
+        wrapped.push_str(&SharedString::from("\n}"));
+        let mut character_stack = CharacterStack::new(wrapped, None);
+        return parse_data(&mut character_stack);
+    }
+
+    fn steps(selector: &Data) -> Vec<Data> {
+        match selector {
+            Data::Path(steps) => return steps.iter().cloned().collect(),
+            other => return vec![other.clone()],
+        }
+    }
+
+    fn find_entry<'node>(node: &'node DocumentNode, step: &Data) -> Status<Option<&'node DocumentEntry>> {
+        if let Data::Integer(index) = step {
+            if node.entries.iter().all(|entry| entry.key.is_none()) {
+                ensure!(*index > 0, IndexOutOfBounds, step.clone(), integer!(node.entries.len() as i64));
+                return success!(node.entries.get(*index as usize - 1));
+            }
+        }
+        return success!(node.entries.iter().find(|entry| entry.key.as_ref() == Some(step)));
+    }
+
+    // returns the entry at the selector and the node containing it
+    fn locate(&self, selector: &Data) -> Status<(&DocumentNode, Option<&DocumentEntry>)> {
+        let steps = Document::steps(selector);
+        let mut node = &self.root;
+
+        for (index, step) in steps.iter().enumerate() {
+            let entry = confirm!(Document::find_entry(node, step));
+            if index == steps.len() - 1 {
+                return success!((node, entry));
+            }
+
+            let entry = expect!(entry, string!("missing entry {} in document", step.serialize()));
+            node = &entry.value;
+        }
+        return error!(string!("document selector may not be empty"));
+    }
+
+    fn replace(&mut self, start: usize, end: usize, text: &SharedString) -> Status<()> {
+        let mut source = substring(&self.source, 0, start);
+        source.push_str(text);
+        source.push_str(&substring(&self.source, end, self.source.len()));
+        *self = confirm!(Document::parse(source));
+        return success!(());
+    }
+
+    fn line_indent(&self, index: usize) -> SharedString {
+        let mut line_start = index;
+        while line_start > 0 && !self.source[line_start - 1].is_newline() {
+            line_start -= 1;
+        }
+
+        let mut indent = SharedString::new();
+        for character in substring(&self.source, line_start, index).chars() {
+            match character.as_char() {
+                ' ' | '\t' => indent.push(*character),
+                _other => break,
+            }
+        }
+        return indent;
+    }
+
+    pub fn set(&mut self, selector: &Data, value: &Data) -> Status<()> {
+        let steps = Document::steps(selector);
+        let (node, entry) = confirm!(self.locate(selector));
+
+        if let Some(entry) = entry {
+            let (start, end) = (entry.value.start, entry.value.end);
+            return self.replace(start, end, &value.serialize());
+        }
+
+        let key = steps.last().unwrap();
+        let is_root = std::ptr::eq(node, &self.root);
+        ensure!(node.entries.iter().all(|entry| entry.key.is_some()), string!("items can only be added to maps"));
+        let mut text = SharedString::new();
+
+        // new entries follow the layout of the last entry
+        let insert = match node.entries.last() {
+            Some(last) => {
+                match substring(&self.source, node.start, last.start).chars().any(|character| character.is_newline()) || is_root {
+                    true => {
+                        text.push(Character::from_char('\n'));
+                        text.push_str(&self.line_indent(last.start));
+                    }
+                    false => text.push(Character::from_char(' ')),
+                }
+                last.value.end
+            }
+            None if is_root => {
+                if !self.source.is_empty() && !self.source[self.source.len() - 1].is_newline() {
+                    text.push(Character::from_char('\n'));
+                }
+                self.source.len()
+            }
+            None => {
+                text.push(Character::from_char(' '));
+                node.start + 1
+            }
+        };
+
+        text.push_str(&key.serialize());
+        text.push(Character::from_char(' '));
+        text.push_str(&value.serialize());
+        return self.replace(insert, insert, &text);
+    }
+
+    pub fn remove(&mut self, selector: &Data) -> Status<()> {
+        let (_node, entry) = confirm!(self.locate(selector));
+        let entry = expect!(entry, string!("missing entry {} in document", selector.serialize()));
+        let (start, mut end) = (entry.leading, entry.value.end);
+
+        // a line that only contained the entry is removed entirely
+        if start == 0 || self.source[start - 1].is_newline() {
+            let mut line_end = end;
+            while line_end < self.source.len() && (self.source[line_end].as_char() == ' ' || self.source[line_end].as_char() == '\t' || self.source[line_end].as_char() == '\r') {
+                line_end += 1;
+            }
+
+            if line_end == self.source.len() || self.source[line_end].is_newline() {
+                end = (line_end + 1).min(self.source.len());
+            }
+        }
+
+        return self.replace(start, end, &SharedString::new());
+    }
+
+    pub fn contains(&self, selector: &Data) -> Status<bool> {
+        let (_node, entry) = confirm!(self.locate(selector));
+        return success!(entry.is_some());
+    }
+
+    // the trivia in front of an entry is read with the same lexer the data parser skips it with
+    pub fn comments(&self, selector: &Data) -> Status<Vec<SharedString>> {
+        let (_node, entry) = confirm!(self.locate(selector));
+        let entry = expect!(entry, string!("missing entry {} in document", selector.serialize()));
+        let mut character_stack = CharacterStack::new(substring(&self.source, entry.leading, entry.start), None);
+        let mut skipped = Vec::new();
+        confirm!(update_comments(&mut character_stack, Some(&mut skipped)));
+
+        let mut comments = Vec::new();
+        for comment in skipped.iter() {
+            comments.extend(comment.unicode().lines().map(|line| line.trim().to_string()).filter(|line| !line.is_empty()));
+        }
+        return success!(comments.into_iter().map(|comment| SharedString::from(&comment)).collect());
+    }
+}
@@ -0,0 +1,225 @@
+use internal::*;
+use debug::*;
+
+fn skip_whitespace(character_stack: &mut CharacterStack) {
+    while let Some(character) = character_stack.peek(0) {
+        match character.as_unicode() {
+            ' ' | '\t' | '\r' | '\n' => character_stack.advance(1),
+            _other => return,
+        }
+    }
+}
+
+fn expect_word(character_stack: &mut CharacterStack, word: &str, value: Data) -> Status<Data> {
+    ensure!(character_stack.check_string(&SharedString::from(word)), string!("invalid json value, expected {}", word));
+    return success!(value);
+}
+
+fn collect_hex(character_stack: &mut CharacterStack) -> Status<u32> {
+    let mut code = String::new();
+    for _ in 0..4 {
+        let character = expect!(character_stack.pop(), UnterminatedEscapeSequence);
+        code.push(character.as_unicode());
+    }
+
+    match u32::from_str_radix(&code, 16) {
+        Ok(code) => return success!(code),
+        Err(_) => return error!(InvalidEscapeSequence, string!("\\u{}", code)),
+    }
+}
+
+fn collect_string(character_stack: &mut CharacterStack) -> Status<SharedString> {
+    let mut literal = SharedString::new();
+    while let Some(character) = character_stack.pop() {
+        match character.as_unicode() {
+
+            '\\' => {
+                let next = expect!(character_stack.pop(), UnterminatedEscapeSequence);
+                match next.as_unicode() {
+                    '\"' => literal.push(Character::from_char('\"')),
+                    '\\' => literal.push(Character::from_char('\\')),
+                    '/' => literal.push(Character::from_char('/')),
+                    'b' => literal.push(Character::from_code(8)),
+                    'f' => literal.push(Character::from_code(12)),
+                    'n' => literal.push(Character::from_char('\n')),
+                    'r' => literal.push(Character::from_char('\r')),
+                    't' => literal.push(Character::from_char('\t')),
+                    'u' => {
+                        let mut code = confirm!(collect_hex(character_stack));
+
+                        // surrogate pairs are combined into a single code point
+                        if code >= 0xD800 && code < 0xDC00 && character_stack.check_string(&SharedString::from("\\u")) {
+                            let low = confirm!(collect_hex(character_stack));
+                            ensure!(low >= 0xDC00 && low < 0xE000, InvalidEscapeSequence, string!("\\u{:04x}", low));
+                            code = 0x10000 + ((code - 0xD800) << 10) + (low - 0xDC00);
+                        }
+                        literal.push(Character::from_code(code));
+                    },
+                    invalid => return error!(InvalidEscapeSequence, string!("\\{}", invalid)),
+                }
+            },
+
+            '\"' => return success!(literal),
+
+            _other => literal.push(character),
+        }
+    }
+    return error!(UnterminatedToken, identifier!("string"));
+}
+
+fn collect_number(character_stack: &mut CharacterStack) -> Status<Data> {
+    let mut number = String::new();
+    while let Some(character) = character_stack.peek(0) {
+        match character.as_unicode() {
+            '0'..='9' | '-' | '+' | '.' | 'e' | 'E' => number.push(character.as_unicode()),
+            _other => break,
+        }
+        character_stack.advance(1);
+    }
+
+    if !number.contains(|character| character == '.' || character == 'e' || character == 'E') {
+        if let Ok(integer) = number.parse::<i64>() {
+            return success!(integer!(integer));
+        }
+    }
+
+    match number.parse::<f64>() {
+        Ok(float) => return success!(float!(float)),
+        Err(_) => return error!(string!("invalid json number {}", number)),
+    }
+}
+
+fn json_key(key: SharedString, tagged: bool) -> Status<Data> {
+    if tagged {
+        let mut character_stack = CharacterStack::new(key, None);
+        return parse_data(&mut character_stack);
+    }
+
+    let is_identifier = !key.is_empty() && !key.chars().next().unwrap().is_digit() && CharacterStack::new(SharedString::new(), None).is_pure(&key);
+    match is_identifier {
+        true => return success!(identifier!(String, key)),
+        false => return success!(string!(String, key)),
+    }
+}
+
+// single entry objects with a $ key are tagged values that json has no native type for
+fn untag_value(tag: &SharedString, value: Data) -> Status<Data> {
+    match tag.printable().as_str() {
+        "$identifier" => return success!(identifier!(String, unpack_string!(&value))),
+        "$keyword" => return success!(keyword!(String, unpack_string!(&value))),
+        "$character" => return success!(character!(code, unpack_integer!(&value) as u32)),
+        "$path" => return success!(path!(unpack_list!(&value))),
+        "$map" => return success!(value),
+        "$float" => {
+            match unpack_string!(&value).printable().as_str() {
+                "inf" => return success!(float!(std::f64::INFINITY)),
+                "-inf" => return success!(float!(std::f64::NEG_INFINITY)),
+                "nan" => return success!(float!(std::f64::NAN)),
+                other => return error!(string!("invalid tagged float {}", other)),
+            }
+        },
+        other => return error!(string!("invalid json tag {}", other)),
+    }
+}
+
+fn parse_object(character_stack: &mut CharacterStack, tagged: bool, untag: bool) -> Status<Data> {
+    let mut map = DataMap::new();
+
+    skip_whitespace(character_stack);
+    if character_stack.check('}') {
+        return success!(map!(map));
+    }
+
+    loop {
+        skip_whitespace(character_stack);
+        ensure!(character_stack.check('\"'), string!("json object keys must be strings"));
+        let raw_key = confirm!(collect_string(character_stack));
+
+        skip_whitespace(character_stack);
+        ensure!(character_stack.check(':'), string!("expected : after json object key"));
+
+        // maps with keys starting with $ are always wrapped, so any other $ key is a tag
+        if tagged && untag && raw_key.chars().next().map(|character| character.as_unicode() == '$').unwrap_or(false) {
+            ensure!(map.len() == 0, string!("json tag {} must be the only entry", raw_key));
+            let value = match raw_key.printable().as_str() == "$map" {
+                true => {
+                    skip_whitespace(character_stack);
+                    ensure!(character_stack.check('{'), string!("expected object after $map"));
+                    confirm!(parse_object(character_stack, tagged, false))
+                }
+                false => confirm!(parse_json(character_stack, tagged)),
+            };
+
+            skip_whitespace(character_stack);
+            ensure!(character_stack.check('}'), string!("json tag {} must be the only entry", raw_key));
+            return untag_value(&raw_key, value);
+        }
+
+        let value = confirm!(parse_json(character_stack, tagged));
+        let key = confirm!(json_key(raw_key, tagged));
+        if let Some(previous) = map.insert(key.clone(), value) {
+            return error!(InexplicitOverwrite, key, previous);
+        }
+
+        skip_whitespace(character_stack);
+        if character_stack.check('}') {
+            return success!(map!(map));
+        }
+        ensure!(character_stack.check(','), string!("expected , or }} in json object"));
+    }
+}
+
+pub fn parse_json(character_stack: &mut CharacterStack, tagged: bool) -> Status<Data> {
+    skip_whitespace(character_stack);
+    let character = expect!(character_stack.peek(0), string!("expected json value"));
+
+    match character.as_unicode() {
+
+        '{' => {
+            character_stack.advance(1);
+            return parse_object(character_stack, tagged, true);
+        },
+
+        '[' => {
+            character_stack.advance(1);
+            let mut items = SharedVector::new();
+
+            skip_whitespace(character_stack);
+            if character_stack.check(']') {
+                return success!(list!(items));
+            }
+
+            loop {
+                items.push(confirm!(parse_json(character_stack, tagged)));
+                skip_whitespace(character_stack);
+                if character_stack.check(']') {
+                    return success!(list!(items));
+                }
+                ensure!(character_stack.check(','), string!("expected , or ] in json array"));
+            }
+        },
+
+        '\"' => {
+            character_stack.advance(1);
+            return success!(string!(String, confirm!(collect_string(character_stack))));
+        },
+
+        't' => return expect_word(character_stack, "true", boolean!(true)),
+
+        'f' => return expect_word(character_stack, "false", boolean!(false)),
+
+        'n' => return expect_word(character_stack, "null", identifier!("none")),
+
+        '-' | '0'..='9' => return collect_number(character_stack),
+
+        invalid => return error!(string!("unexpected character {} in json", invalid)),
+    }
+}
+
+pub fn parse_json_document(source: SharedString, tagged: bool) -> Status<Data> {
+    let mut character_stack = CharacterStack::new(source, None);
+    let data = confirm!(parse_json(&mut character_stack, tagged));
+    skip_whitespace(&mut character_stack);
+    ensure!(character_stack.is_empty(), string!("trailing characters after json value"));
+    return success!(data);
+}
@@ -1,10 +1,14 @@
 mod number;
 mod stack;
+mod json;
+mod document;
 
 use internal::*;
 use debug::*;
 
-pub use self::stack::CharacterStack;
+pub use self::stack::{ CharacterStack, CHUNK_SIZE };
+pub use self::json::parse_json_document;
+pub use self::document::Document;
 
 use self::number::parse_number;
 
@@ -72,26 +76,31 @@ fn check_path(character_stack: &mut CharacterStack, first: Data) -> Status<Data>
     }
 }
 
-fn update(character_stack: &mut CharacterStack) -> Status<()> {
-    'update: while let Some(character) = character_stack.peek(0) {
-        match character.as_char() {
+// skips whitespace and comments, the text of every skipped comment is added to comments if requested
+fn update_comments(character_stack: &mut CharacterStack, mut comments: Option<&mut Vec<SharedString>>) -> Status<()> {
+    while let Some(character) = character_stack.peek(0) {
+        match character.as_unicode() {
 
             '@' => {
                 character_stack.advance(1);
+                let block = character_stack.check('@');
+                let mut comment = SharedString::new();
+                let mut terminated = false;
 
-                if character_stack.check('@') {
-                    while let Some(character) = character_stack.pop() {
-                        if character.as_char() == '@' && character_stack.check('@') {
-                            continue 'update;
-                        }
+                while let Some(character) = character_stack.pop() {
+                    if block && character.as_unicode() == '@' && character_stack.check('@') {
+                        terminated = true;
+                        break;
+                    }
+                    if !block && character.as_unicode() == '\n' {
+                        break;
                     }
-                    return error!(UnterminatedToken, identifier!("comment")); // TODO
+                    comment.push(character);
                 }
 
-                while let Some(character) = character_stack.pop() {
-                    if character.as_char() == '\n' {
-                        continue 'update;
-                    }
+                ensure!(!block || terminated, UnterminatedToken, identifier!("comment"));
+                if let Some(ref mut comments) = comments {
+                    comments.push(comment);
                 }
             },
 
@@ -104,6 +113,10 @@ fn update(character_stack: &mut CharacterStack) -> Status<()> {
     return success!(());
 }
 
+fn update(character_stack: &mut CharacterStack) -> Status<()> {
+    return update_comments(character_stack, None);
+}
+
 pub fn parse_data(character_stack: &mut CharacterStack) -> Status<Data> {
     confirm!(update(character_stack));
     if let Some(character) = character_stack.peek(0) {
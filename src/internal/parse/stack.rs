@@ -1,16 +1,100 @@
 use internal::*;
 use debug::*;
 
+use std::fmt::{ Formatter, Result, Debug };
+use std::io::{ Read, ErrorKind };
+
+pub static CHUNK_SIZE: usize = 4096;
+
+// pulls characters from a reader in chunks, incomplete utf-8 sequences wait for the next chunk
+struct CharacterReader {
+    reader:         Box<dyn Read>,
+    chunk_size:     usize,
+    pending:        Vec<u8>,
+    finished:       bool,
+}
+
+impl CharacterReader {
+
+    fn new(reader: Box<dyn Read>, chunk_size: usize) -> Self {
+        Self {
+            reader:         reader,
+            chunk_size:     chunk_size,
+            pending:        Vec::new(),
+            finished:       false,
+        }
+    }
+
+    fn read_chunk(&mut self) -> Status<Vec<Character>> {
+        let mut buffer = vec![0; self.chunk_size];
+        let length = loop {
+            match self.reader.read(&mut buffer) {
+                Ok(length) => break length,
+                Err(ref error) if error.kind() == ErrorKind::Interrupted => continue,
+                Err(error) => {
+                    self.finished = true;
+                    return error!(string!("failed to read source: {}", error));
+                }
+            }
+        };
+
+        self.finished = length == 0;
+        self.pending.extend_from_slice(&buffer[..length]);
+
+        let mut characters = Vec::new();
+        loop {
+            match std::str::from_utf8(&self.pending) {
+                Ok(valid) => {
+                    characters.extend(valid.chars().map(Character::from_char));
+                    self.pending.clear();
+                    return success!(characters);
+                }
+                Err(error) => {
+                    let valid_length = error.valid_up_to();
+                    characters.extend(std::str::from_utf8(&self.pending[..valid_length]).unwrap().chars().map(Character::from_char));
+
+                    match error.error_len() {
+                        Some(invalid_length) => {
+                            characters.push(Character::from_char(std::char::REPLACEMENT_CHARACTER));
+                            self.pending.drain(..valid_length + invalid_length);
+                        }
+                        None if self.finished => {
+                            characters.push(Character::from_char(std::char::REPLACEMENT_CHARACTER));
+                            self.pending.clear();
+                            return success!(characters);
+                        }
+                        None => {
+                            self.pending.drain(..valid_length);
+                            return success!(characters);
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
+impl Debug for CharacterReader {
+
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+        return write!(f, "CharacterReader({} byte chunks, {} pending, finished: {})", self.chunk_size, self.pending.len(), self.finished);
+    }
+}
+
 #[derive(Debug)]
 pub struct CharacterStack {
     source:         SharedString,
-    save_states:    Vec<(usize, Vec<Position>)>,
+    reader:         Option<CharacterReader>,
+    read_error:     Option<Error>,
+    offset:         usize,
+    save_states:    Vec<(usize, usize)>,
     index:          usize,
-    file:           Option<SharedString>,
+    file:           usize,
     breaking:       Vec<Character>,
     non_breaking:   Vec<Character>,
+    classes:        Vec<CharacterClass>,
     signature:      Vec<SharedString>,
-    positions:      Vec<Position>,
+    start:          usize,
 }
 
 impl CharacterStack {
@@ -24,55 +108,150 @@ impl CharacterStack {
         }
         breaking.append(&mut vec!['.', '-', ':', '!', '#', '[', ']', '{', '}', '\'', '\"', 127 as char]);
 
+        // stacks without a file only read data and never hand out positions, so they are not registered
+        let file = match file_path {
+            Some(..) => SourceMap::register(&file_path, &source),
+            None => SourceMap::UNREGISTERED,
+        };
+
         Self {
-            positions:      vec![Position::new(file_path.clone(), source.clone(), 0, 1, 1, 0)],
+            file:           file,
             source:         source,
+            reader:         None,
+            read_error:     None,
+            offset:         0,
             save_states:    Vec::new(),
             index:          0,
-            file:           file_path,
             breaking:       breaking.into_iter().map(|character| Character::from_char(character)).collect(), // unly, please fix
             non_breaking:   non_breaking.into_iter().map(|character| Character::from_char(character)).collect(), // unly, please fix
+            classes:        Vec::new(),
             signature:      Vec::new(),
+            start:          0,
         }
     }
 
     pub fn derive(&self, source: SharedString, file_path: Option<SharedString>) -> Self {
         Self {
-            positions:      vec![Position::new(file_path.clone(), source.clone(), 0, 1, 1, 0)],
+            file:           SourceMap::register(&file_path, &source),
             source:         source,
+            reader:         None,
+            read_error:     None,
+            offset:         0,
+            save_states:    Vec::new(),
+            index:          0,
+            breaking:       self.breaking.clone(),
+            non_breaking:   self.non_breaking.clone(),
+            classes:        self.classes.clone(),
+            signature:      self.signature.clone(),
+            start:          0,
+        }
+    }
+
+    // switches the character configuration while keeping the current source and position
+    pub fn adopt(&mut self, other: &CharacterStack) {
+        self.breaking = other.breaking.clone();
+        self.non_breaking = other.non_breaking.clone();
+        self.classes = other.classes.clone();
+        self.signature = other.signature.clone();
+    }
+
+    pub fn derive_reader(&self, reader: Box<dyn Read>, file_path: Option<SharedString>, chunk_size: usize) -> Self {
+        Self {
+            file:           SourceMap::register_stream(&file_path),
+            source:         SharedString::new(),
+            reader:         Some(CharacterReader::new(reader, chunk_size)),
+            read_error:     None,
+            offset:         0,
             save_states:    Vec::new(),
             index:          0,
-            file:           file_path,
             breaking:       self.breaking.clone(),
             non_breaking:   self.non_breaking.clone(),
+            classes:        self.classes.clone(),
             signature:      self.signature.clone(),
+            start:          0,
         }
     }
 
+    fn loaded(&self) -> usize {
+        return self.offset + self.source.len();
+    }
+
+    // makes sure the characters up to the given index are loaded, returns false if the source ends before
+    fn fill(&mut self, length: usize) -> bool {
+        while self.loaded() < length {
+            let characters = match &mut self.reader {
+                Some(reader) if !reader.finished => reader.read_chunk(),
+                _other => return false,
+            };
+
+            // the source ends where reading failed, the error is picked up by whoever drives the stack
+            let characters = match characters {
+                Status::Success(characters) => characters,
+                Status::Error(error) => {
+                    self.read_error = Some(error);
+                    return false;
+                }
+            };
+
+            SourceMap::extend_stream(self.file, &characters);
+            for character in characters.into_iter() {
+                self.source.push(character);
+            }
+        }
+        return true;
+    }
+
+    // only keeps the characters that can still be read or restored in memory
+    fn discard(&mut self) {
+        // the previous character is kept to detect the start of a line
+        let keep = self.index.min(self.start).saturating_sub(1);
+        let chunk_size = match &self.reader {
+            Some(reader) => reader.chunk_size,
+            None => return,
+        };
+
+        if !self.save_states.is_empty() || keep - self.offset < chunk_size {
+            return;
+        }
+
+        self.source = self.source.chars().skip(keep - self.offset).cloned().collect();
+        self.offset = keep;
+    }
+
+    pub fn take_read_error(&mut self) -> Option<Error> {
+        return self.read_error.take();
+    }
+
+    fn clamped_index(&self) -> usize {
+        return self.index.min(self.loaded());
+    }
+
     pub fn current_position(&self) -> Position {
-        let position = self.positions.last().unwrap();
-        return Position::new(self.file.clone(), self.source.clone(), position.index, position.line, position.character + position.length, 0);
+        let index = self.clamped_index();
+        return Position::new(self.file, index, index);
     }
 
     pub fn start_positions(&mut self) {
-        let position = self.positions.last().unwrap();
-        self.positions = vec![Position::new(self.file.clone(), self.source.clone(), self.index, position.line, position.character + position.length, 0)];
+        self.start = self.clamped_index();
+        self.discard();
     }
 
     pub fn final_positions(&self) -> Vec<Position> {
-        let mut positions = self.positions.clone();
-        positions.retain(|position| position.length != 0);
-        return positions;
+        let end = self.clamped_index();
+        match end > self.start {
+            true => return vec![Position::new(self.file, self.start, end)],
+            false => return Vec::new(),
+        }
     }
 
     pub fn save(&mut self) {
-        self.save_states.push((self.index, self.positions.clone()));
+        self.save_states.push((self.index, self.start));
     }
 
     pub fn restore(&mut self) {
-        let (index, positions) = self.save_states.pop().unwrap();
+        let (index, start) = self.save_states.pop().unwrap();
         self.index = index;
-        self.positions = positions;
+        self.start = start;
     }
 
     pub fn drop(&mut self) {
@@ -80,34 +259,44 @@ impl CharacterStack {
     }
 
     pub fn advance(&mut self, offset: usize) {
-        for _offset in 0..offset {
-            if self.source.len() > self.index {
-                self.positions.last_mut().unwrap().length += 1;
-                if self.source[self.index].as_char() == '\n' {
-                    let line = self.positions.last().unwrap().line;
-                    self.positions.push(Position::new(self.file.clone(), self.source.clone(), self.index + 1, line + 1, 1, 0));
-                }
-            }
-            self.index += 1;
+        self.index += offset;
+    }
+
+    pub fn is_line_start(&mut self) -> bool {
+        if self.index == 0 {
+            return true;
         }
+        return self.fill(self.index) && self.source[self.index - 1 - self.offset].is_newline();
     }
 
-    pub fn is_empty(&self) -> bool {
-        self.source.len() <= self.index
+    pub fn is_empty(&mut self) -> bool {
+        return !self.fill(self.index + 1);
     }
 
-    pub fn peek(&self, offset: usize) -> Option<Character> {
-        match self.source.len() > self.index + offset {
-            true => return Some(self.source[self.index + offset]),
+    pub fn peek(&mut self, offset: usize) -> Option<Character> {
+        match self.fill(self.index + offset + 1) {
+            true => return Some(self.source[self.index + offset - self.offset]),
             false => return None,
         }
     }
 
+    // streamed sources are read until the limit or the end of the source
+    pub fn remaining(&mut self, limit: usize) -> SharedString {
+        let mut remaining = SharedString::new();
+        while remaining.len() < limit {
+            match self.peek(remaining.len()) {
+                Some(character) => remaining.push(character),
+                None => break,
+            }
+        }
+        return remaining;
+    }
+
     pub fn pop(&mut self) -> Option<Character> {
         if self.is_empty() {
             return None;
         }
-        let character = self.source[self.index];
+        let character = self.source[self.index - self.offset];
         self.advance(1);
         return Some(character);
     }
@@ -144,12 +333,12 @@ impl CharacterStack {
     }
 
     pub fn check_string(&mut self, compare: &SharedString) -> bool {
-        if self.index + compare.len() > self.source.len() {
+        if !self.fill(self.index + compare.len()) {
             return false;
         }
 
         for (index, character) in compare.chars().enumerate() {
-            if self.source[self.index + index] != *character {
+            if self.source[self.index + index - self.offset] != *character {
                 return false;
             }
         }
@@ -178,6 +367,20 @@ impl CharacterStack {
         return success!(());
     }
 
+    // characters registered individually take precedence over breaking classes
+    pub fn register_breaking_class(&mut self, class: CharacterClass) {
+        if !self.classes.contains(&class) {
+            self.classes.push(class);
+        }
+    }
+
+    pub fn register_classes(&mut self, classes: &Vec<CharacterClass>) -> Status<()> {
+        for character in classes.iter().filter_map(|class| class.single()) {
+            confirm!(self.register_non_breaking(character));
+        }
+        return success!(());
+    }
+
     pub fn register_signature(&mut self, signature: SharedString) -> Status<()> {
         if !self.signature.contains(&signature) {
             self.signature.push(signature);
@@ -195,7 +398,10 @@ impl CharacterStack {
     }
 
     pub fn is_breaking(&self, compare: Character) -> bool {
-        return self.breaking.contains(&compare);
+        if self.breaking.contains(&compare) {
+            return true;
+        }
+        return !self.non_breaking.contains(&compare) && CharacterClass::any_contains(&self.classes, compare);
     }
 
     pub fn is_pure(&self, compare: &SharedString) -> bool {
@@ -0,0 +1,328 @@
+use internal::*;
+use debug::*;
+
+use std::collections::HashMap;
+
+static MAGIC: &'static [u8] = b"SMKB";
+static VERSION: u64 = 1;
+
+const MAP: u8 = 0;
+const LIST: u8 = 1;
+const PATH: u8 = 2;
+const IDENTIFIER: u8 = 3;
+const KEYWORD: u8 = 4;
+const STRING: u8 = 5;
+const CHARACTER: u8 = 6;
+const INTEGER: u8 = 7;
+const FLOAT: u8 = 8;
+const FALSE: u8 = 9;
+const TRUE: u8 = 10;
+const REFERENCE: u8 = 11;
+
+fn write_varint(output: &mut Vec<u8>, mut value: u64) {
+    while value >= 0x80 {
+        output.push((value as u8 & 0x7F) | 0x80);
+        value >>= 7;
+    }
+    output.push(value as u8);
+}
+
+struct BinaryWriter {
+    strings:        Vec<Vec<u32>>,
+    string_lookup:  HashMap<Vec<u32>, usize>,
+    containers:     HashMap<(u8, usize), usize>,
+    completed:      usize,
+    output:         Vec<u8>,
+}
+
+impl BinaryWriter {
+
+    fn new() -> Self {
+        Self {
+            strings:        Vec::new(),
+            string_lookup:  HashMap::new(),
+            containers:     HashMap::new(),
+            completed:      0,
+            output:         Vec::new(),
+        }
+    }
+
+    fn write_string(&mut self, tag: u8, string: &SharedString) {
+        let codes: Vec<u32> = string.chars().map(|character| character.code()).collect();
+        let index = match self.string_lookup.get(&codes) {
+            Some(index) => *index,
+            None => {
+                self.strings.push(codes.clone());
+                self.string_lookup.insert(codes, self.strings.len() - 1);
+                self.strings.len() - 1
+            }
+        };
+
+        self.output.push(tag);
+        write_varint(&mut self.output, index as u64);
+    }
+
+    // shared containers are written once and referenced by the order they were completed in,
+    // keyed by tag as well since a list and a path may share the same vector
+    fn write_reference(&mut self, tag: u8, identity: Option<usize>) -> bool {
+        if let Some(identity) = identity {
+            if let Some(index) = self.containers.get(&(tag, identity)) {
+                self.output.push(REFERENCE);
+                write_varint(&mut self.output, *index as u64);
+                return true;
+            }
+        }
+        return false;
+    }
+
+    fn complete_container(&mut self, tag: u8, identity: Option<usize>) {
+        if let Some(identity) = identity {
+            self.containers.insert((tag, identity), self.completed);
+            self.completed += 1;
+        }
+    }
+
+    fn write_items(&mut self, tag: u8, items: &SharedVector<Data>) {
+        let identity = (!items.is_empty()).then_some(items.identity());
+        if self.write_reference(tag, identity) {
+            return;
+        }
+
+        self.output.push(tag);
+        write_varint(&mut self.output, items.len() as u64);
+        for item in items.iter() {
+            self.write_data(&item);
+        }
+        self.complete_container(tag, identity);
+    }
+
+    fn write_data(&mut self, data: &Data) {
+        match data {
+
+            Data::Map(map) => {
+                let identity = map.identity();
+                if self.write_reference(MAP, identity) {
+                    return;
+                }
+
+                self.output.push(MAP);
+                write_varint(&mut self.output, map.len() as u64);
+                for (key, value) in map.iter() {
+                    self.write_data(&key);
+                    self.write_data(&value);
+                }
+                self.complete_container(MAP, identity);
+            }
+
+            Data::List(items) => self.write_items(LIST, items),
+
+            Data::Path(steps) => self.write_items(PATH, steps),
+
+            Data::Identifier(identifier) => self.write_string(IDENTIFIER, identifier),
+
+            Data::Keyword(keyword) => self.write_string(KEYWORD, keyword),
+
+            Data::String(string) => self.write_string(STRING, string),
+
+            Data::Character(character) => {
+                self.output.push(CHARACTER);
+                write_varint(&mut self.output, character.code() as u64);
+            }
+
+            Data::Integer(integer) => {
+                self.output.push(INTEGER);
+                write_varint(&mut self.output, ((integer << 1) ^ (integer >> 63)) as u64);
+            }
+
+            Data::Float(float) => {
+                self.output.push(FLOAT);
+                self.output.extend_from_slice(&float.to_bits().to_le_bytes());
+            }
+
+            Data::Boolean(boolean) => self.output.push(if *boolean { TRUE } else { FALSE }),
+        }
+    }
+
+    fn finish(self) -> Vec<u8> {
+        let mut binary = Vec::from(MAGIC);
+        write_varint(&mut binary, VERSION);
+        write_varint(&mut binary, self.strings.len() as u64);
+
+        for string in self.strings.iter() {
+            write_varint(&mut binary, string.len() as u64);
+            for code in string.iter() {
+                write_varint(&mut binary, *code as u64);
+            }
+        }
+
+        binary.extend_from_slice(&self.output);
+        return binary;
+    }
+}
+
+struct BinaryReader<'source> {
+    source:         &'source [u8],
+    offset:         usize,
+    strings:        Vec<SharedString>,
+    containers:     Vec<Data>,
+}
+
+impl<'source> BinaryReader<'source> {
+
+    fn new(source: &'source [u8]) -> Self {
+        Self {
+            source:         source,
+            offset:         0,
+            strings:        Vec::new(),
+            containers:     Vec::new(),
+        }
+    }
+
+    fn read_byte(&mut self) -> Status<u8> {
+        let byte = expect!(self.source.get(self.offset), string!("unexpected end of binary data"));
+        self.offset += 1;
+        return success!(*byte);
+    }
+
+    fn read_varint(&mut self) -> Status<u64> {
+        let mut value = 0;
+        let mut shift = 0;
+
+        loop {
+            let byte = confirm!(self.read_byte());
+            ensure!(shift < 64, string!("invalid varint in binary data"));
+            value |= ((byte & 0x7F) as u64) << shift;
+            if byte & 0x80 == 0 {
+                return success!(value);
+            }
+            shift += 7;
+        }
+    }
+
+    fn read_length(&mut self) -> Status<usize> {
+        let length = confirm!(self.read_varint()) as usize;
+        ensure!(length <= self.source.len(), string!("invalid length {} in binary data", length));
+        return success!(length);
+    }
+
+    fn read_header(&mut self) -> Status<()> {
+        ensure!(self.source.starts_with(MAGIC), string!("binary data is missing its header"));
+        self.offset = MAGIC.len();
+
+        let version = confirm!(self.read_varint());
+        ensure!(version == VERSION, string!("unsupported binary version {}", version));
+
+        let string_count = confirm!(self.read_length());
+        for _ in 0..string_count {
+            let length = confirm!(self.read_length());
+            let mut string = SharedString::new();
+            for _ in 0..length {
+                string.push(Character::from_code(confirm!(self.read_varint()) as u32));
+            }
+            self.strings.push(string);
+        }
+        return success!(());
+    }
+
+    fn read_string(&mut self) -> Status<SharedString> {
+        let index = confirm!(self.read_varint()) as usize;
+        let string = expect!(self.strings.get(index), string!("invalid string index {} in binary data", index));
+        return success!(string.clone());
+    }
+
+    fn read_items(&mut self) -> Status<SharedVector<Data>> {
+        let length = confirm!(self.read_length());
+        let mut items = SharedVector::new();
+        for _ in 0..length {
+            items.push(confirm!(self.read_data()));
+        }
+        return success!(items);
+    }
+
+    fn complete_container(&mut self, data: Data, shared: bool) -> Data {
+        if shared {
+            self.containers.push(data.clone());
+        }
+        return data;
+    }
+
+    fn read_data(&mut self) -> Status<Data> {
+        match confirm!(self.read_byte()) {
+
+            MAP => {
+                let length = confirm!(self.read_length());
+                let mut map = DataMap::new();
+                for _ in 0..length {
+                    let key = confirm!(self.read_data());
+                    let value = confirm!(self.read_data());
+                    if let Some(previous) = map.insert(key.clone(), value) {
+                        return error!(InexplicitOverwrite, key, previous);
+                    }
+                }
+                return success!(self.complete_container(map!(map), length != 0));
+            }
+
+            LIST => {
+                let items = confirm!(self.read_items());
+                let shared = !items.is_empty();
+                return success!(self.complete_container(list!(items), shared));
+            }
+
+            PATH => {
+                let steps = confirm!(self.read_items());
+                let shared = !steps.is_empty();
+                return success!(self.complete_container(path!(steps), shared));
+            }
+
+            IDENTIFIER => return success!(identifier!(String, confirm!(self.read_string()))),
+
+            KEYWORD => return success!(keyword!(String, confirm!(self.read_string()))),
+
+            STRING => return success!(string!(String, confirm!(self.read_string()))),
+
+            CHARACTER => return success!(character!(code, confirm!(self.read_varint()) as u32)),
+
+            INTEGER => {
+                let value = confirm!(self.read_varint());
+                return success!(integer!(((value >> 1) as i64) ^ -((value & 1) as i64)));
+            }
+
+            FLOAT => {
+                let mut bytes = [0; 8];
+                for byte in bytes.iter_mut() {
+                    *byte = confirm!(self.read_byte());
+                }
+                return success!(float!(f64::from_bits(u64::from_le_bytes(bytes))));
+            }
+
+            FALSE => return success!(boolean!(false)),
+
+            TRUE => return success!(boolean!(true)),
+
+            REFERENCE => {
+                let index = confirm!(self.read_varint()) as usize;
+                let container = expect!(self.containers.get(index), string!("invalid reference {} in binary data", index));
+                return success!(container.clone());
+            }
+
+            invalid => return error!(string!("invalid tag {} in binary data", invalid)),
+        }
+    }
+}
+
+impl Data {
+
+    pub fn to_binary(&self) -> Vec<u8> {
+        let mut writer = BinaryWriter::new();
+        writer.write_data(self);
+        return writer.finish();
+    }
+
+    pub fn from_binary(source: &[u8]) -> Status<Data> {
+        let mut reader = BinaryReader::new(source);
+        confirm!(reader.read_header());
+        let data = confirm!(reader.read_data());
+        ensure!(reader.offset == source.len(), string!("trailing bytes after binary data"));
+        return success!(data);
+    }
+}
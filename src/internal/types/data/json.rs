@@ -0,0 +1,124 @@
+use internal::*;
+use debug::*;
+
+use super::serialize::serialize_float;
+
+pub fn json_string(source: &SharedString) -> Status<String> {
+    let mut string = String::from("\"");
+    for character in source.chars() {
+        match character.code() {
+            34 => string.push_str("\\\""),
+            92 => string.push_str("\\\\"),
+            8 => string.push_str("\\b"),
+            12 => string.push_str("\\f"),
+            10 => string.push_str("\\n"),
+            13 => string.push_str("\\r"),
+            9 => string.push_str("\\t"),
+            code if code < 32 => string.push_str(&format!("\\u{:04x}", code)),
+            code => match std::char::from_u32(code) {
+                Some(character) => string.push(character),
+                None if code <= 0xFFFF => string.push_str(&format!("\\u{:04x}", code)),
+                None => return error!(string!("character code {} can not be represented in json", code)),
+            },
+        }
+    }
+    string.push('"');
+    return success!(string);
+}
+
+fn json_tag(tag: &str, value: String) -> String {
+    return format!("{{\"${}\":{}}}", tag, value);
+}
+
+fn json_key(key: &Data, tagged: bool) -> SharedString {
+    if tagged {
+        return key.serialize();
+    }
+
+    match key {
+        Data::String(string) => return string.clone(),
+        Data::Identifier(identifier) => return identifier.clone(),
+        Data::Keyword(keyword) => return format_shared!("#{}", keyword.unicode()),
+        Data::Character(character) => return character.to_string(),
+        other => return other.serialize(),
+    }
+}
+
+fn json_float(float: f64, tagged: bool) -> Status<String> {
+    if float.is_finite() {
+        return success!(serialize_float(float).printable());
+    }
+
+    ensure!(tagged, string!("float {} can not be represented in json", float));
+    let name = match float.is_nan() {
+        true => "nan",
+        false if float > 0.0 => "inf",
+        false => "-inf",
+    };
+    return success!(json_tag("float", format!("\"{}\"", name)));
+}
+
+fn json_items(items: &SharedVector<Data>, tagged: bool) -> Status<String> {
+    let mut converted = Vec::new();
+    for item in items.iter() {
+        converted.push(confirm!(item.to_json(tagged)).unicode());
+    }
+    return success!(format!("[{}]", converted.join(",")));
+}
+
+fn json_map(map: &DataMap, tagged: bool) -> Status<String> {
+    let mut entries = Vec::new();
+    let mut wrap = false;
+
+    for (key, value) in map.iter() {
+        let key = json_key(key, tagged);
+        wrap |= key.chars().next().map(|character| character.as_unicode() == '$').unwrap_or(false);
+        entries.push(format!("{}:{}", confirm!(json_string(&key)), confirm!(value.to_json(tagged)).unicode()));
+    }
+
+    // keys starting with $ would be mistaken for a tag, so the map is tagged itself
+    let object = format!("{{{}}}", entries.join(","));
+    match tagged && wrap {
+        true => return success!(json_tag("map", object)),
+        false => return success!(object),
+    }
+}
+
+impl Data {
+
+    pub fn to_json(&self, tagged: bool) -> Status<SharedString> {
+        let json = match self {
+            Data::Map(map) => confirm!(json_map(map, tagged)),
+            Data::List(items) => confirm!(json_items(items, tagged)),
+            Data::String(string) => confirm!(json_string(string)),
+            Data::Integer(integer) => integer.to_string(),
+            Data::Float(float) => confirm!(json_float(*float, tagged)),
+            Data::Boolean(boolean) => boolean.to_string(),
+
+            Data::Path(steps) => match tagged {
+                true => json_tag("path", confirm!(json_items(steps, tagged))),
+                false => confirm!(json_items(steps, tagged)),
+            },
+
+            Data::Identifier(identifier) => match tagged {
+                true => json_tag("identifier", confirm!(json_string(identifier))),
+                false => confirm!(json_string(identifier)),
+            },
+
+            Data::Keyword(keyword) => match tagged {
+                true => json_tag("keyword", confirm!(json_string(keyword))),
+                false => confirm!(json_string(&format_shared!("#{}", keyword.unicode()))),
+            },
+
+            Data::Character(character) => match tagged {
+                true => json_tag("character", character.code().to_string()),
+                false => confirm!(json_string(&character.to_string())),
+            },
+        };
+        return success!(SharedString::from(&json));
+    }
+
+    pub fn from_json(source: &SharedString, tagged: bool) -> Status<Data> {
+        return parse_json_document(source.clone(), tagged);
+    }
+}
@@ -1,5 +1,7 @@
 mod index;
 mod serialize;
+mod json;
+mod binary;
 
 use internal::*;
 use debug::*;
@@ -7,6 +9,9 @@ use debug::*;
 use self::serialize::*;
 use self::index::*;
 
+pub use self::serialize::PrettyOptions;
+pub use self::json::json_string;
+
 #[derive(Clone, Debug, PartialEq)]
 pub enum Data {
     Map(DataMap),
@@ -1152,8 +1157,8 @@ impl Data {
     pub fn values(&self) -> Status<Data> {
         match self {
             Data::Map(map) => return success!(list!(map.values().cloned().collect())),
-            Data::List(items) => return success!(list!(items.iter().cloned().collect())),
-            Data::Path(steps) => return success!(list!(steps.iter().cloned().collect())),
+            Data::List(items) => return success!(list!(items.clone())),
+            Data::Path(steps) => return success!(list!(steps.clone())),
             Data::String(string) => return success!(list!(string.chars().map(|character| character!(*character)).collect())),
             Data::Identifier(identifier) => return success!(list!(identifier.chars().map(|character| character!(*character)).collect())),
             Data::Keyword(keyword) => return success!(list!(keyword.chars().map(|character| character!(*character)).collect())),
@@ -1319,6 +1324,10 @@ impl Data {
         }
     }
 
+    pub fn serialize_pretty(&self, options: &PrettyOptions, column: usize) -> SharedString {
+        return SharedString::from(&serialize_pretty(self, options, 0, column));
+    }
+
     pub fn set_entry(&mut self, key: &Data, data: Data, overwrite: bool) -> Status<bool> {
         if let Data::Map(ref mut map) = self {
             if let Some(entry) = map.get_mut(&key) {
@@ -1,4 +1,41 @@
 use internal::*;
+use debug::*;
+
+#[derive(Debug, Clone)]
+pub struct PrettyOptions {
+    pub indent:     usize,
+    pub width:      usize,
+    pub comments:   bool,
+}
+
+impl PrettyOptions {
+
+    pub fn new() -> Self {
+        Self {
+            indent:     4,
+            width:      80,
+            comments:   false,
+        }
+    }
+
+    pub fn deserialize(serialized: &Data) -> Status<Self> {
+        let mut options = PrettyOptions::new();
+
+        if let Some(indent) = confirm!(serialized.index(&identifier!("indent"))) {
+            options.indent = unpack_integer!(&indent) as usize;
+        }
+
+        if let Some(width) = confirm!(serialized.index(&identifier!("width"))) {
+            options.width = unpack_integer!(&width) as usize;
+        }
+
+        if let Some(comments) = confirm!(serialized.index(&identifier!("comments"))) {
+            options.comments = unpack_boolean!(&comments);
+        }
+
+        return success!(options);
+    }
+}
 
 pub fn serialize_float(value: f64) -> SharedString {
     let mut string = value.to_string();
@@ -52,3 +89,77 @@ pub fn serialize_path(source: &SharedVector<Data>) -> SharedString {
 
     return string;
 }
+
+// flat lengths are computed once per node, so deciding whether a container fits does not serialize its children again
+struct FlatLength {
+    length:     usize,
+    children:   Vec<FlatLength>,
+}
+
+fn flat_length(source: &Data) -> FlatLength {
+    match source {
+
+        Data::Map(map) if map.len() > 0 => {
+            let mut length = 3;
+            let mut children = Vec::new();
+            for (key, value) in map.iter() {
+                let child = flat_length(&value);
+                length += key.serialize().len() + child.length + 2;
+                children.push(child);
+            }
+            return FlatLength { length: length, children: children };
+        }
+
+        Data::List(items) if !items.is_empty() => {
+            let mut length = 3;
+            let mut children = Vec::new();
+            for item in items.iter() {
+                let child = flat_length(&item);
+                length += child.length + 1;
+                children.push(child);
+            }
+            return FlatLength { length: length, children: children };
+        }
+
+        other => return FlatLength { length: other.serialize().len(), children: Vec::new() },
+    }
+}
+
+// containers that do not fit into the remaining width are split into one entry per line
+fn pretty_node(source: &Data, flat: &FlatLength, options: &PrettyOptions, indent: usize, column: usize) -> String {
+    if column + flat.length <= options.width {
+        return source.serialize().unicode();
+    }
+
+    let padding = " ".repeat((indent + 1) * options.indent);
+    let closing_padding = " ".repeat(indent * options.indent);
+
+    match source {
+
+        Data::Map(map) if map.len() > 0 => {
+            let mut string = String::from("{\n");
+            for ((key, value), child) in map.iter().zip(flat.children.iter()) {
+                let key = key.serialize();
+                let value = pretty_node(&value, child, options, indent + 1, padding.len() + key.len() + 1);
+                string.push_str(&format!("{}{} {}\n", padding, key.unicode(), value));
+            }
+            string.push_str(&format!("{}}}", closing_padding));
+            return string;
+        }
+
+        Data::List(items) if !items.is_empty() => {
+            let mut string = String::from("[\n");
+            for (item, child) in items.iter().zip(flat.children.iter()) {
+                string.push_str(&format!("{}{}\n", padding, pretty_node(&item, child, options, indent + 1, padding.len())));
+            }
+            string.push_str(&format!("{}]", closing_padding));
+            return string;
+        }
+
+        _other => return source.serialize().unicode(),
+    }
+}
+
+pub fn serialize_pretty(source: &Data, options: &PrettyOptions, indent: usize, column: usize) -> String {
+    return pretty_node(source, &flat_length(source), options, indent, column);
+}
@@ -78,6 +78,11 @@ impl<K: Compare + Clone, T: Clone> Map<K, T> {
         return MutableMapValueIterator::new(&mut self.root, self.size);
     }
 
+    // maps sharing the same root node have the same identity
+    pub fn identity(&self) -> Option<usize> {
+        return self.root.map(|root| root.as_ptr() as usize);
+    }
+
     pub fn drain(&mut self) -> MapDrainIterator<K, T> {
         return MapDrainIterator::new(&self.root, self.size);
     }
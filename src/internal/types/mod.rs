@@ -14,10 +14,10 @@ mod pass;
 pub use self::note::Note;
 pub use self::token::{ TokenType, Token };
 pub use self::registry::VariantRegistry;
-pub use self::rules::{ Rules, Action };
+pub use self::rules::{ Rules, Action, CharacterClass, Pattern };
 pub use self::vector::*;
 pub use self::string::{ Character, SharedString };
 pub use self::map::*;
-pub use self::data::Data;
+pub use self::data::{ Data, PrettyOptions, json_string };
 pub use self::compare::{ Compare, Relation };
 pub use self::pass::Pass;
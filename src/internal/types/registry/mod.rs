@@ -5,6 +5,7 @@ use debug::*;
 pub struct VariantRegistry {
     pub operators: Vec<SharedString>,
     pub keywords: Vec<SharedString>,
+    pub patterns: Vec<SharedString>,
     pub rules: Rules,
     pub has_characters: bool,
     pub has_comments: bool,
@@ -12,6 +13,8 @@ pub struct VariantRegistry {
     pub has_floats: bool,
     pub has_strings: bool,
     pub has_negatives: bool,
+    pub has_layout: bool,
+    pub has_interpolation: bool,
 }
 
 impl VariantRegistry {
@@ -20,6 +23,7 @@ impl VariantRegistry {
         Self {
             operators: Vec::new(),
             keywords: Vec::new(),
+            patterns: Vec::new(),
             rules: Rules::new(),
             has_characters: false,
             has_comments: false,
@@ -27,6 +31,8 @@ impl VariantRegistry {
             has_floats: false,
             has_strings: false,
             has_negatives: false,
+            has_layout: false,
+            has_interpolation: false,
         }
     }
 
@@ -39,6 +45,8 @@ impl VariantRegistry {
         map.insert(identifier!("floats"), boolean!(self.has_floats));
         map.insert(identifier!("strings"), boolean!(self.has_strings));
         map.insert(identifier!("negatives"), boolean!(self.has_negatives));
+        map.insert(identifier!("layout"), boolean!(self.has_layout));
+        map.insert(identifier!("interpolation"), boolean!(self.has_interpolation));
 
         let operator_list = self.operators.into_iter().map(|operator| identifier!(String, operator)).collect();
         map.insert(identifier!("operators"), list!(operator_list));
@@ -46,6 +54,9 @@ impl VariantRegistry {
         let keyword_list = self.keywords.into_iter().map(|keyword| identifier!(String, keyword)).collect();
         map.insert(identifier!("keywords"), list!(keyword_list));
 
+        let pattern_list = self.patterns.into_iter().map(|pattern| identifier!(String, pattern)).collect();
+        map.insert(identifier!("patterns"), list!(pattern_list));
+
         let rules_map = self.rules.serialize();
         map.insert(identifier!("rules"), rules_map);
 
@@ -80,6 +91,14 @@ impl VariantRegistry {
         let has_negatives = expect!(has_negatives, string!("variant registry may not be missing negatives field"));
         variant_registry.has_negatives = unpack_boolean!(&has_negatives);
 
+        let has_layout = confirm!(serialized.index(&identifier!("layout")));
+        let has_layout = expect!(has_layout, string!("variant registry may not be missing layout field"));
+        variant_registry.has_layout = unpack_boolean!(&has_layout);
+
+        let has_interpolation = confirm!(serialized.index(&identifier!("interpolation")));
+        let has_interpolation = expect!(has_interpolation, string!("variant registry may not be missing interpolation field"));
+        variant_registry.has_interpolation = unpack_boolean!(&has_interpolation);
+
         let operator_list = confirm!(serialized.index(&identifier!("operators")));
         let operator_list = expect!(operator_list, string!("variant registry may not be missing operators field"));
 
@@ -94,6 +113,13 @@ impl VariantRegistry {
             variant_registry.keywords.push(unpack_identifier!(keyword));
         }
 
+        let pattern_list = confirm!(serialized.index(&identifier!("patterns")));
+        let pattern_list = expect!(pattern_list, string!("variant registry may not be missing patterns field"));
+
+        for pattern in unpack_list!(&pattern_list).iter() {
+            variant_registry.patterns.push(unpack_identifier!(pattern));
+        }
+
         let rules = confirm!(serialized.index(&identifier!("rules")));
         let rules = expect!(rules, string!("variant registry may not be missing rules field"));
         variant_registry.rules = confirm!(Rules::deserialize(&rules));
@@ -125,6 +151,14 @@ impl VariantRegistry {
         return success!(());
     }
 
+    pub fn validate_patterns(&self, filters: &Vec<SharedString>) -> Status<()> {
+        ensure!(!self.patterns.is_empty(), string!("tokenizer does not support patterns"));
+        for filter in filters.iter() {
+            ensure!(self.is_pattern(filter), string!("{} is not a valid pattern", filter));
+        }
+        return success!(());
+    }
+
     pub fn validate_identifiers(&self, filters: &Vec<SharedString>) -> Status<()> {
         ensure!(self.has_identifiers(), string!("tokenizer does not support identifiers"));
         for filter in filters.iter() {
@@ -157,6 +191,16 @@ impl VariantRegistry {
         return success!(());
     }
 
+    pub fn validate_layout(&self) -> Status<()> {
+        ensure!(self.has_layout, string!("tokenizer does not support layout"));
+        return success!(());
+    }
+
+    pub fn validate_interpolation(&self) -> Status<()> {
+        ensure!(self.has_interpolation, string!("tokenizer does not support interpolation"));
+        return success!(());
+    }
+
     pub fn validate_strings(&self) -> Status<()> {
         ensure!(self.has_strings, string!("tokenizer does not support strings"));
         return success!(());
@@ -197,6 +241,34 @@ impl VariantRegistry {
         return self.keywords.iter().find(|keyword| **keyword == *compare).is_some();
     }
 
+    pub fn is_pattern(&self, compare: &SharedString) -> bool {
+        return self.patterns.iter().find(|pattern| **pattern == *compare).is_some();
+    }
+
+    pub fn merge(&mut self, other: &VariantRegistry) {
+        for operator in other.operators.iter() {
+            self.register_operator(operator.clone());
+        }
+
+        for keyword in other.keywords.iter() {
+            self.register_keyword(keyword.clone());
+        }
+
+        for pattern in other.patterns.iter() {
+            self.register_pattern(pattern.clone());
+        }
+
+        self.rules.merge(&other.rules);
+        self.has_characters |= other.has_characters;
+        self.has_comments |= other.has_comments;
+        self.has_integers |= other.has_integers;
+        self.has_floats |= other.has_floats;
+        self.has_strings |= other.has_strings;
+        self.has_negatives |= other.has_negatives;
+        self.has_layout |= other.has_layout;
+        self.has_interpolation |= other.has_interpolation;
+    }
+
     pub fn register_operator(&mut self, operator: SharedString) {
         if !self.is_operator(&operator) {
             self.operators.push(operator);
@@ -209,6 +281,12 @@ impl VariantRegistry {
         }
     }
 
+    pub fn register_pattern(&mut self, pattern: SharedString) {
+        if !self.is_pattern(&pattern) {
+            self.patterns.push(pattern);
+        }
+    }
+
     pub fn avalible_keywords(&self) -> Vec<SharedString> {
         return self.keywords.clone();
     }
@@ -216,4 +294,8 @@ impl VariantRegistry {
     pub fn avalible_operators(&self) -> Vec<SharedString> {
         return self.operators.clone();
     }
+
+    pub fn avalible_patterns(&self) -> Vec<SharedString> {
+        return self.patterns.clone();
+    }
 }
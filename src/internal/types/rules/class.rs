@@ -0,0 +1,114 @@
+use internal::*;
+use debug::*;
+
+use super::unicode::{ XID_START, XID_CONTINUE, DECIMAL_DIGIT };
+use std::cmp::Ordering;
+
+fn in_table(table: &[(u32, u32)], code: u32) -> bool {
+    return table.binary_search_by(|(start, end)| match (*start > code, *end < code) {
+        (true, _) => Ordering::Greater,
+        (_, true) => Ordering::Less,
+        _other => Ordering::Equal,
+    }).is_ok();
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum CharacterClass {
+    XidStart,
+    XidContinue,
+    Letter,
+    Uppercase,
+    Lowercase,
+    Digit,
+    Whitespace,
+    Range(Character, Character),
+}
+
+impl CharacterClass {
+
+    pub fn serialize(&self) -> Data {
+        match self {
+            CharacterClass::XidStart => return keyword!("xid_start"),
+            CharacterClass::XidContinue => return keyword!("xid_continue"),
+            CharacterClass::Letter => return keyword!("letter"),
+            CharacterClass::Uppercase => return keyword!("uppercase"),
+            CharacterClass::Lowercase => return keyword!("lowercase"),
+            CharacterClass::Digit => return keyword!("digit"),
+            CharacterClass::Whitespace => return keyword!("whitespace"),
+            CharacterClass::Range(start, end) if start == end => return character!(*start),
+            CharacterClass::Range(start, end) => return list!(vector![character!(*start), character!(*end)]),
+        }
+    }
+
+    pub fn deserialize(serialized: &Data) -> Status<Self> {
+        match serialized {
+
+            Data::Keyword(keyword) => {
+                match keyword.printable().as_str() {
+                    "xid_start" => return success!(CharacterClass::XidStart),
+                    "xid_continue" => return success!(CharacterClass::XidContinue),
+                    "letter" => return success!(CharacterClass::Letter),
+                    "uppercase" => return success!(CharacterClass::Uppercase),
+                    "lowercase" => return success!(CharacterClass::Lowercase),
+                    "digit" => return success!(CharacterClass::Digit),
+                    "whitespace" => return success!(CharacterClass::Whitespace),
+                    invalid => return error!(string!("invalid character class {}", invalid)),
+                }
+            },
+
+            Data::Character(character) => return success!(CharacterClass::Range(*character, *character)),
+
+            Data::List(items) => {
+                ensure!(items.len() == 2, string!("character ranges need a start and an end character"));
+                let start = unpack_character!(&items[0]);
+                let end = unpack_character!(&items[1]);
+                ensure!(start <= end, string!("character range {} to {} is empty", start.serialize(), end.serialize()));
+                return success!(CharacterClass::Range(start, end));
+            },
+
+            _other => return error!(ExpectedFound, expected_list!["keyword", "character", "list"], serialized.clone()),
+        }
+    }
+
+    pub fn deserialize_list(serialized: &Data) -> Status<Vec<Self>> {
+        let mut classes = Vec::new();
+        for class in unpack_list!(serialized).into_iter() {
+            classes.push(confirm!(CharacterClass::deserialize(&class)));
+        }
+        return success!(classes);
+    }
+
+    pub fn serialize_list(classes: &Vec<Self>) -> Data {
+        return list!(classes.iter().map(|class| class.serialize()).collect());
+    }
+
+    pub fn contains(&self, character: Character) -> bool {
+        let code = character.code();
+        let character = match std::char::from_u32(code) {
+            Some(character) => character,
+            None => return false,
+        };
+
+        match self {
+            CharacterClass::XidStart => return in_table(&XID_START, code),
+            CharacterClass::XidContinue => return in_table(&XID_CONTINUE, code),
+            CharacterClass::Letter => return character.is_alphabetic(),
+            CharacterClass::Uppercase => return character.is_uppercase(),
+            CharacterClass::Lowercase => return character.is_lowercase(),
+            CharacterClass::Digit => return in_table(&DECIMAL_DIGIT, code),
+            CharacterClass::Whitespace => return character.is_whitespace(),
+            CharacterClass::Range(start, end) => return code >= start.code() && code <= end.code(),
+        }
+    }
+
+    pub fn any_contains(classes: &Vec<Self>, character: Character) -> bool {
+        return classes.iter().any(|class| class.contains(character));
+    }
+
+    pub fn single(&self) -> Option<Character> {
+        match self {
+            CharacterClass::Range(start, end) if start == end => return Some(*start),
+            _other => return None,
+        }
+    }
+}
@@ -1,13 +1,19 @@
 mod action;
+mod class;
+mod pattern;
+mod unicode;
 
 use internal::*;
 use debug::*;
 
 pub use self::action::Action;
+pub use self::class::CharacterClass;
+pub use self::pattern::Pattern;
 
 #[derive(Debug, Clone)]
 pub struct Rules {
     rules:      Vec<(SharedString, Action)>,
+    classes:    Vec<(Vec<CharacterClass>, Option<Vec<CharacterClass>>, Action)>,
 }
 
 impl Rules {
@@ -15,6 +21,7 @@ impl Rules {
     pub fn new() -> Self {
         Self {
             rules:      Vec::new(),
+            classes:    Vec::new(),
         }
     }
 
@@ -25,6 +32,20 @@ impl Rules {
             map.insert(string!(String, signature), action.serialize());
         }
 
+        if !self.classes.is_empty() {
+            let mut classes = SharedVector::new();
+            for (start, follow, action) in self.classes.into_iter() {
+                let mut class_map = Map::new();
+                class_map.insert(identifier!("start"), CharacterClass::serialize_list(&start));
+                if let Some(follow) = follow {
+                    class_map.insert(identifier!("continue"), CharacterClass::serialize_list(&follow));
+                }
+                class_map.insert(identifier!("action"), action.serialize());
+                classes.push(map!(class_map));
+            }
+            map.insert(keyword!("classes"), list!(classes));
+        }
+
         return map!(map);
     }
 
@@ -32,6 +53,21 @@ impl Rules {
         let mut rules = Self::new();
 
         for (signature, action) in confirm!(serialized.pairs()).iter() {
+            if *signature == keyword!("classes") {
+                for class in unpack_list!(action).into_iter() {
+                    let start = confirm!(class.index(&identifier!("start")));
+                    let start = expect!(start, string!("character class rule may not be missing start field"));
+                    let follow = match confirm!(class.index(&identifier!("continue"))) {
+                        Some(follow) => Some(confirm!(CharacterClass::deserialize_list(&follow))),
+                        None => None,
+                    };
+                    let class_action = confirm!(class.index(&identifier!("action")));
+                    let class_action = expect!(class_action, string!("character class rule may not be missing action field"));
+                    rules.add_class(confirm!(CharacterClass::deserialize_list(&start)), follow, confirm!(Action::deserialize(&class_action)));
+                }
+                continue;
+            }
+
             let signature = unpack_string!(signature);
             let action = confirm!(Action::deserialize(action));
             rules.add(signature, action);
@@ -41,13 +77,17 @@ impl Rules {
     }
 
     pub fn has_mapping(&self, string: &str) -> bool {
-        return self.rules.iter().find(|(_, action)| action.is_mapped_to(string)).is_some();
+        return self.rules.iter().find(|(_, action)| action.is_mapped_to(string)).is_some() || self.classes.iter().find(|(_, _, action)| action.is_mapped_to(string)).is_some();
     }
 
     pub fn has_mapping_to(&self, source_signature: &SharedString, string: &str) -> bool {
         return self.rules.iter().find(|(signature, action)| *source_signature == *signature && action.is_mapped_to(string)).is_some();
     }
 
+    pub fn patterns(&self) -> Vec<SharedString> {
+        return self.rules.iter().map(|(pattern, _)| pattern.clone()).collect();
+    }
+
     fn contains(&self, new: &SharedString) -> bool {
         for (pattern, _rule) in self.rules.iter() {
             if pattern == new {
@@ -68,12 +108,57 @@ impl Rules {
         return success!(());
     }
 
+    pub fn merge(&mut self, other: &Rules) {
+        for (pattern, action) in other.rules.iter() {
+            if !self.contains(pattern) {
+                self.add(pattern.clone(), action.clone());
+            }
+        }
+
+        for class in other.classes.iter() {
+            if !self.classes.contains(class) {
+                self.classes.push(class.clone());
+            }
+        }
+    }
+
+    // classes are checked in the order they were added, after all literal patterns
+    pub fn add_class(&mut self, start: Vec<CharacterClass>, follow: Option<Vec<CharacterClass>>, action: Action) {
+        self.classes.push((start, follow, action));
+    }
+
+    fn class_length(follow: &Option<Vec<CharacterClass>>, string: &SharedString) -> usize {
+        match follow {
+            Some(follow) => return 1 + string.chars().skip(1).take_while(|character| CharacterClass::any_contains(follow, **character)).count(),
+            None => return string.len(),
+        }
+    }
+
     pub fn check_stack(&self, stack: &mut CharacterStack) -> Option<(SharedString, Action)> {
         for (pattern, action) in self.rules.iter() {
             if stack.check_string(pattern) {
                  return Some((pattern.clone(), action.clone()));
             }
         }
+
+        for (start, follow, action) in self.classes.iter() {
+            let first = stack.peek(0)?;
+            if CharacterClass::any_contains(start, first) {
+                let mut matched = first.to_string();
+                stack.advance(1);
+
+                if let Some(follow) = follow {
+                    while let Some(character) = stack.peek(0) {
+                        if !CharacterClass::any_contains(follow, character) {
+                            break;
+                        }
+                        matched.push(character);
+                        stack.advance(1);
+                    }
+                }
+                return Some((matched, action.clone()));
+            }
+        }
         return None;
     }
 
@@ -83,10 +168,28 @@ impl Rules {
                 return Some((pattern.clone(), action.clone()));
             }
         }
+
+        if let Some((matched, action)) = self.check_class(string) {
+            if matched.len() == string.len() {
+                return Some((matched, action));
+            }
+        }
         return None;
     }
 
-    pub fn check_prefix(&self, string: &SharedString) -> Option<(SharedString, Action)> {
+    // returns the longest prefix of the string that the first matching class accepts
+    pub fn check_class(&self, string: &SharedString) -> Option<(SharedString, Action)> {
+        let first = *string.chars().next()?;
+        for (start, follow, action) in self.classes.iter() {
+            if CharacterClass::any_contains(start, first) {
+                let length = Rules::class_length(follow, string);
+                return Some((string.chars().take(length).cloned().collect(), action.clone()));
+            }
+        }
+        return None;
+    }
+
+    pub fn check_pattern_prefix(&self, string: &SharedString) -> Option<(SharedString, Action)> {
         for (pattern, action) in self.rules.iter() {
             if let Some(position) = string.find(pattern) {
                 if position == 0 {
@@ -96,4 +199,19 @@ impl Rules {
         }
         return None;
     }
+
+    // only matches if no literal pattern is a prefix of the string
+    pub fn check_class_prefix(&self, string: &SharedString) -> Option<(SharedString, Action)> {
+        match self.check_pattern_prefix(string) {
+            Some(_) => return None,
+            None => return self.check_class(string),
+        }
+    }
+
+    pub fn check_prefix(&self, string: &SharedString) -> Option<(SharedString, Action)> {
+        match self.check_pattern_prefix(string) {
+            Some(matched) => return Some(matched),
+            None => return self.check_class(string),
+        }
+    }
 }
@@ -0,0 +1,291 @@
+use internal::*;
+use debug::*;
+
+use std::collections::BTreeSet;
+
+#[derive(Clone, Debug, PartialEq)]
+enum PatternNode {
+    Set(Vec<CharacterClass>, bool),
+    Group(Vec<Vec<PatternNode>>),
+    Repeat(Box<PatternNode>, usize, Option<usize>),
+}
+
+// a small regular expression subset: literals, ., escapes, [sets], (groups), | and the quantifiers * + ? {n,m}
+#[derive(Clone, Debug, PartialEq)]
+pub struct Pattern {
+    source:         SharedString,
+    alternatives:   Vec<Vec<PatternNode>>,
+}
+
+impl Pattern {
+
+    pub fn new(source: SharedString) -> Status<Self> {
+        let characters: Vec<Character> = source.chars().cloned().collect();
+        let mut index = 0;
+        let alternatives = confirm!(Pattern::parse_alternatives(&characters, &mut index), Tag, string!(String, source.clone()));
+        ensure!(index == characters.len(), string!("unmatched closing parenthesis in pattern {}", source));
+
+        let pattern = Self {
+            source:         source,
+            alternatives:   alternatives,
+        };
+        ensure!(!pattern.matches_empty(), string!("pattern {} may not match an empty string", pattern.source));
+        return success!(pattern);
+    }
+
+    pub fn serialize(&self) -> Data {
+        return string!(String, self.source.clone());
+    }
+
+    fn parse_alternatives(characters: &Vec<Character>, index: &mut usize) -> Status<Vec<Vec<PatternNode>>> {
+        let mut alternatives = vec![Vec::new()];
+
+        while *index < characters.len() {
+            let node = match characters[*index].as_unicode() {
+
+                ')' => break,
+
+                '|' => {
+                    *index += 1;
+                    alternatives.push(Vec::new());
+                    continue;
+                }
+
+                '(' => {
+                    *index += 1;
+                    if characters.get(*index).map(|character| character.as_unicode()) == Some('?') {
+                        ensure!(characters.get(*index + 1).map(|character| character.as_unicode()) == Some(':'), string!("only non capturing groups are supported"));
+                        *index += 2;
+                    }
+                    let group = confirm!(Pattern::parse_alternatives(characters, index));
+                    ensure!(*index < characters.len(), string!("unterminated group"));
+                    *index += 1;
+                    PatternNode::Group(group)
+                }
+
+                '[' => confirm!(Pattern::parse_set(characters, index)),
+
+                '.' => {
+                    *index += 1;
+                    PatternNode::Set(vec![CharacterClass::Range(Character::from_char('\n'), Character::from_char('\n'))], true)
+                }
+
+                '\\' => {
+                    *index += 1;
+                    let (classes, negated) = confirm!(Pattern::parse_escape(characters, index));
+                    PatternNode::Set(classes, negated)
+                }
+
+                '*' | '+' | '?' | '{' => return error!(string!("quantifier without a preceding item")),
+
+                _other => {
+                    let character = characters[*index];
+                    *index += 1;
+                    PatternNode::Set(vec![CharacterClass::Range(character, character)], false)
+                }
+            };
+
+            let node = confirm!(Pattern::parse_quantifier(characters, index, node));
+            alternatives.last_mut().unwrap().push(node);
+        }
+
+        return success!(alternatives);
+    }
+
+    fn parse_escape(characters: &Vec<Character>, index: &mut usize) -> Status<(Vec<CharacterClass>, bool)> {
+        let character = *expect!(characters.get(*index), string!("unterminated escape sequence"));
+        *index += 1;
+
+        let word = vec![CharacterClass::Letter, CharacterClass::Digit, CharacterClass::Range(Character::from_char('_'), Character::from_char('_'))];
+        match character.as_unicode() {
+            'd' => return success!((vec![CharacterClass::Digit], false)),
+            'D' => return success!((vec![CharacterClass::Digit], true)),
+            'w' => return success!((word, false)),
+            'W' => return success!((word, true)),
+            's' => return success!((vec![CharacterClass::Whitespace], false)),
+            'S' => return success!((vec![CharacterClass::Whitespace], true)),
+            'n' => return success!((vec![CharacterClass::Range(Character::from_char('\n'), Character::from_char('\n'))], false)),
+            't' => return success!((vec![CharacterClass::Range(Character::from_char('\t'), Character::from_char('\t'))], false)),
+            _other => return success!((vec![CharacterClass::Range(character, character)], false)),
+        }
+    }
+
+    fn parse_set(characters: &Vec<Character>, index: &mut usize) -> Status<PatternNode> {
+        let mut classes = Vec::new();
+        *index += 1;
+
+        let negated = characters.get(*index).map(|character| character.as_unicode()) == Some('^');
+        if negated {
+            *index += 1;
+        }
+
+        loop {
+            let character = *expect!(characters.get(*index), string!("unterminated character set"));
+            *index += 1;
+
+            let start = match character.as_unicode() {
+                ']' if !classes.is_empty() => break,
+                '\\' => {
+                    let (escaped, escaped_negated) = confirm!(Pattern::parse_escape(characters, index));
+                    ensure!(!escaped_negated, string!("negated escapes may not be used in character sets"));
+                    match escaped.len() == 1 && escaped[0].single().is_some() {
+                        true => escaped[0].single().unwrap(),
+                        false => {
+                            classes.extend(escaped.into_iter());
+                            continue;
+                        }
+                    }
+                }
+                _other => character,
+            };
+
+            let is_range = characters.get(*index).map(|character| character.as_unicode()) == Some('-') && characters.get(*index + 1).map(|character| character.as_unicode() != ']').unwrap_or(false);
+            if is_range {
+                let end = characters[*index + 1];
+                *index += 2;
+                ensure!(start <= end, string!("character range {} to {} is empty", start.serialize(), end.serialize()));
+                classes.push(CharacterClass::Range(start, end));
+            } else {
+                classes.push(CharacterClass::Range(start, start));
+            }
+        }
+
+        return success!(PatternNode::Set(classes, negated));
+    }
+
+    fn parse_number(characters: &Vec<Character>, index: &mut usize) -> Option<usize> {
+        let mut number = String::new();
+        while let Some(character) = characters.get(*index) {
+            if !character.as_unicode().is_ascii_digit() {
+                break;
+            }
+            number.push(character.as_unicode());
+            *index += 1;
+        }
+        return number.parse::<usize>().ok();
+    }
+
+    fn parse_quantifier(characters: &Vec<Character>, index: &mut usize, node: PatternNode) -> Status<PatternNode> {
+        let (minimum, maximum) = match characters.get(*index).map(|character| character.as_unicode()) {
+            Some('*') => (0, None),
+            Some('+') => (1, None),
+            Some('?') => (0, Some(1)),
+
+            Some('{') => {
+                *index += 1;
+                let minimum = expect!(Pattern::parse_number(characters, index), string!("expected repetition count"));
+                let maximum = match characters.get(*index).map(|character| character.as_unicode()) {
+                    Some(',') => {
+                        *index += 1;
+                        Pattern::parse_number(characters, index)
+                    }
+                    _other => Some(minimum),
+                };
+                ensure!(characters.get(*index).map(|character| character.as_unicode()) == Some('}'), string!("unterminated repetition"));
+                if let Some(maximum) = maximum {
+                    ensure!(minimum <= maximum, string!("repeat minimum {} is bigger than maximum {}", minimum, maximum));
+                }
+                (minimum, maximum)
+            }
+
+            _other => return success!(node),
+        };
+
+        *index += 1;
+        return success!(PatternNode::Repeat(Box::new(node), minimum, maximum));
+    }
+
+    // end offsets are kept in sets, so alternatives and repetitions reaching the same offset are only followed once
+    fn node_ends(node: &PatternNode, character_stack: &mut CharacterStack, offset: usize) -> BTreeSet<usize> {
+        match node {
+
+            PatternNode::Set(classes, negated) => {
+                match character_stack.peek(offset) {
+                    Some(character) if CharacterClass::any_contains(classes, character) != *negated => return std::iter::once(offset + 1).collect(),
+                    _other => return BTreeSet::new(),
+                }
+            }
+
+            PatternNode::Group(alternatives) => {
+                let mut ends = BTreeSet::new();
+                for alternative in alternatives.iter() {
+                    ends.extend(Pattern::sequence_ends(alternative, character_stack, offset));
+                }
+                return ends;
+            }
+
+            PatternNode::Repeat(part, minimum, maximum) => {
+                let mut ends = BTreeSet::new();
+                let mut visited: BTreeSet<usize> = std::iter::once(offset).collect();
+                let mut current = visited.clone();
+                let mut count = 0;
+
+                // positions that were already reached are not repeated again, so empty repetitions terminate
+                loop {
+                    if count >= *minimum {
+                        ends.extend(current.iter().cloned());
+                    }
+
+                    if current.is_empty() || *maximum == Some(count) {
+                        break;
+                    }
+
+                    let mut next = BTreeSet::new();
+                    for position in current.iter() {
+                        next.extend(Pattern::node_ends(part, character_stack, *position));
+                    }
+
+                    if count >= *minimum {
+                        next.retain(|position| !visited.contains(position));
+                    }
+
+                    visited.extend(next.iter().cloned());
+                    current = next;
+                    count += 1;
+                }
+                return ends;
+            }
+        }
+    }
+
+    fn sequence_ends(sequence: &Vec<PatternNode>, character_stack: &mut CharacterStack, offset: usize) -> BTreeSet<usize> {
+        let mut positions: BTreeSet<usize> = std::iter::once(offset).collect();
+        for node in sequence.iter() {
+            let mut next = BTreeSet::new();
+            for position in positions.iter() {
+                next.extend(Pattern::node_ends(node, character_stack, *position));
+            }
+
+            if next.is_empty() {
+                return next;
+            }
+            positions = next;
+        }
+        return positions;
+    }
+
+    fn node_empty(node: &PatternNode) -> bool {
+        match node {
+            PatternNode::Set(..) => return false,
+            PatternNode::Group(alternatives) => return alternatives.iter().any(|alternative| alternative.iter().all(Pattern::node_empty)),
+            PatternNode::Repeat(part, minimum, _) => return *minimum == 0 || Pattern::node_empty(part),
+        }
+    }
+
+    fn matches_empty(&self) -> bool {
+        return self.alternatives.iter().any(|alternative| alternative.iter().all(Pattern::node_empty));
+    }
+
+    // returns the length of the longest match at the current position of the character stack
+    pub fn longest(&self, character_stack: &mut CharacterStack) -> Option<usize> {
+        let mut longest = None;
+        for alternative in self.alternatives.iter() {
+            for length in Pattern::sequence_ends(alternative, character_stack, 0).into_iter() {
+                if length > longest.unwrap_or(0) {
+                    longest = Some(length);
+                }
+            }
+        }
+        return longest;
+    }
+}
@@ -50,6 +50,10 @@ impl Character {
         return self.data as u8 as char; // TODO
     }
 
+    pub fn as_unicode(&self) -> char {
+        return std::char::from_u32(self.data).unwrap_or(std::char::REPLACEMENT_CHARACTER);
+    }
+
     pub fn uppercase(&self) -> Self {
         return Character::from_char(self.as_char().to_ascii_uppercase());
     }
@@ -74,7 +78,7 @@ impl Character {
     }
 
     pub fn is_newline(&self) -> bool {
-        return self.as_char() == '\n';
+        return self.data == '\n' as u32;
     }
 
     pub fn is_whitespace(&self) -> bool {
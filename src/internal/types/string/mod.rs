@@ -55,6 +55,10 @@ impl SharedString {
         return self.data.iter().map(|character| character.as_char()).collect();
     }
 
+    pub fn unicode(&self) -> String {
+        return self.data.iter().map(|character| character.as_unicode()).collect();
+    }
+
     pub fn push(&mut self, character: Character) {
         self.data.push(character);
     }
@@ -4,14 +4,23 @@ use debug::*;
 #[derive(Debug, Clone, PartialEq)]
 pub enum TokenType {
     Comment(SharedString),
+    Documentation(SharedString),
     Keyword(SharedString),
     Operator(SharedString),
     Identifier(SharedString),
+    Contextual(SharedString, SharedString),
     TypeIdentifier(SharedString),
     String(SharedString),
     Character(Character),
     Integer(i64),
     Float(f64),
+    Indent,
+    Dedent,
+    Newline,
+    StringPart(SharedString),
+    InterpolationStart,
+    InterpolationEnd,
+    Custom(SharedString, SharedString),
     Invalid(Error),
     Ignored,
 }
@@ -20,6 +29,7 @@ pub enum TokenType {
 pub struct Token {
     pub token_type: TokenType,
     pub position: Vec<Position>,
+    pub suffix: Option<SharedString>,
 }
 
 impl Token {
@@ -28,44 +38,74 @@ impl Token {
         Self {
             token_type: token_type,
             position: position,
+            suffix: None,
         }
     }
 
+    pub fn with_suffix(mut self, suffix: Option<SharedString>) -> Self {
+        self.suffix = suffix;
+        return self;
+    }
+
     pub fn serialize_position(&self) -> Data {
         return list!(self.position.iter().map(|position| position.serialize_partial()).collect());
     }
 
     pub fn serialize(self, root: &Data, build: &Data) -> Data {
         let serialized_positions = self.serialize_position();
-        match self.token_type {
-            TokenType::Comment(comment) => return list!(vector![keyword!("comment"), string!(String, comment), serialized_positions]),
-            TokenType::Operator(operator) => return list!(vector![keyword!("operator"), string!(String, operator), serialized_positions]),
-            TokenType::Keyword(keyword) => return list!(vector![keyword!("keyword"), identifier!(String, keyword), serialized_positions]),
-            TokenType::Identifier(identifier) => return list!(vector![keyword!("identifier"), identifier!(String, identifier), serialized_positions]),
-            TokenType::TypeIdentifier(type_identifier) => return list!(vector![keyword!("type_identifier"), identifier!(String, type_identifier), serialized_positions]),
-            TokenType::Character(character) => return list!(vector![keyword!("character"), character!(character), serialized_positions]),
-            TokenType::String(string) => return list!(vector![keyword!("string"), string!(String, string), serialized_positions]),
-            TokenType::Integer(integer) => return list!(vector![keyword!("integer"), integer!(integer), serialized_positions]),
-            TokenType::Float(float) => return list!(vector![keyword!("float"), float!(float), serialized_positions]),
-            TokenType::Invalid(error) => return list!(vector![keyword!("invalid"), string!(String, error.display(&Some(root), build)), serialized_positions]),
-            TokenType::Ignored => return list!(vector![keyword!("ignored"), serialized_positions]),
+        let mut serialized = match self.token_type {
+            TokenType::Comment(comment) => vector![keyword!("comment"), string!(String, comment), serialized_positions],
+            TokenType::Documentation(documentation) => vector![keyword!("documentation"), string!(String, documentation), serialized_positions],
+            TokenType::Operator(operator) => vector![keyword!("operator"), string!(String, operator), serialized_positions],
+            TokenType::Keyword(keyword) => vector![keyword!("keyword"), identifier!(String, keyword), serialized_positions],
+            TokenType::Identifier(identifier) => vector![keyword!("identifier"), identifier!(String, identifier), serialized_positions],
+            TokenType::Contextual(identifier, keyword) => vector![keyword!("contextual"), identifier!(String, identifier), identifier!(String, keyword), serialized_positions],
+            TokenType::TypeIdentifier(type_identifier) => vector![keyword!("type_identifier"), identifier!(String, type_identifier), serialized_positions],
+            TokenType::Character(character) => vector![keyword!("character"), character!(character), serialized_positions],
+            TokenType::String(string) => vector![keyword!("string"), string!(String, string), serialized_positions],
+            TokenType::Integer(integer) => vector![keyword!("integer"), integer!(integer), serialized_positions],
+            TokenType::Float(float) => vector![keyword!("float"), float!(float), serialized_positions],
+            TokenType::Indent => vector![keyword!("indent"), serialized_positions],
+            TokenType::Dedent => vector![keyword!("dedent"), serialized_positions],
+            TokenType::Newline => vector![keyword!("newline"), serialized_positions],
+            TokenType::StringPart(string) => vector![keyword!("string_part"), string!(String, string), serialized_positions],
+            TokenType::InterpolationStart => vector![keyword!("interpolation_start"), serialized_positions],
+            TokenType::InterpolationEnd => vector![keyword!("interpolation_end"), serialized_positions],
+            TokenType::Custom(name, text) => vector![keyword!("custom"), identifier!(String, name), string!(String, text), serialized_positions],
+            TokenType::Invalid(error) => vector![keyword!("invalid"), string!(String, error.display(&Some(root), build)), serialized_positions],
+            TokenType::Ignored => vector![keyword!("ignored"), serialized_positions],
         };
+
+        // typed literals keep their type suffix, for example 1u8 or 1.5f32
+        if let Some(suffix) = self.suffix {
+            serialized.push(string!(String, suffix));
+        }
+        return list!(serialized);
     }
 
-    pub fn deserialize(serialized: &Data, file: &Option<SharedString>, source: &SharedString) -> Status<Self> {
+    pub fn deserialize(serialized: &Data, file: usize) -> Status<Self> {
 
         let mut source_list = unpack_list!(serialized);
         let token_type = unpack_keyword!(&source_list.remove(0));
         let token_type = match token_type.serialize().as_str() {
             "comment" => TokenType::Comment(unpack_string!(&source_list.remove(0))),
+            "documentation" => TokenType::Documentation(unpack_string!(&source_list.remove(0))),
             "operator" => TokenType::Operator(unpack_string!(&source_list.remove(0))),
             "keyword" => TokenType::Keyword(unpack_identifier!(&source_list.remove(0))),
             "identifier" => TokenType::Identifier(unpack_identifier!(&source_list.remove(0))),
+            "contextual" => TokenType::Contextual(unpack_identifier!(&source_list.remove(0)), unpack_identifier!(&source_list.remove(0))),
             "type_identifier" => TokenType::TypeIdentifier(unpack_identifier!(&source_list.remove(0))),
             "character" => TokenType::Character(unpack_character!(&source_list.remove(0))),
             "string" => TokenType::String(unpack_string!(&source_list.remove(0))),
             "integer" => TokenType::Integer(unpack_integer!(&source_list.remove(0))),
             "float" => TokenType::Float(unpack_float!(&source_list.remove(0))),
+            "indent" => TokenType::Indent,
+            "dedent" => TokenType::Dedent,
+            "newline" => TokenType::Newline,
+            "string_part" => TokenType::StringPart(unpack_string!(&source_list.remove(0))),
+            "interpolation_start" => TokenType::InterpolationStart,
+            "interpolation_end" => TokenType::InterpolationEnd,
+            "custom" => TokenType::Custom(unpack_identifier!(&source_list.remove(0)), unpack_string!(&source_list.remove(0))),
             "invalid" => TokenType::Invalid(Error::Message(source_list.remove(0))),
             "ignored" => TokenType::Ignored,
             invalid => return error!(string!("invalid token type {}", invalid)),
@@ -73,16 +113,31 @@ impl Token {
 
         let mut positions = Vec::new();
         for position in unpack_list!(&source_list.remove(0)).iter() {
-            positions.push(confirm!(Position::deserialize_partial(position, file, source)));
+            positions.push(confirm!(Position::deserialize_partial(position, file)));
         }
 
-        return success!(Self::new(token_type, positions));
+        let suffix = match source_list.is_empty() {
+            true => None,
+            false => Some(unpack_string!(&source_list.remove(0))),
+        };
+
+        return success!(Self::new(token_type, positions).with_suffix(suffix));
     }
 
     pub fn parsable(&self) -> bool {
         match &self.token_type {
             TokenType::Invalid(..) => panic!("cannot parse invalid tokens"),
             TokenType::Comment(..) => false,
+            TokenType::Documentation(..) => false,
+            TokenType::Ignored => false,
+            _other => true,
+        }
+    }
+
+    pub fn is_content(&self) -> bool {
+        match &self.token_type {
+            TokenType::Comment(..) => false,
+            TokenType::Documentation(..) => false,
             TokenType::Ignored => false,
             _other => true,
         }
@@ -91,16 +146,33 @@ impl Token {
     pub fn to_location(&self) -> Data {
         match &self.token_type {
             TokenType::Comment(..) => panic!(),
+            TokenType::Documentation(..) => panic!(),
             TokenType::Operator(operator) => return Data::Identifier(format_shared!("operator:{}", operator)),
             TokenType::Keyword(keyword) => return Data::Identifier(format_shared!("keyword:{}", keyword)),
             TokenType::Identifier(..) => return identifier!("identifier"),
+            TokenType::Contextual(..) => return identifier!("identifier"),
             TokenType::TypeIdentifier(..) => return identifier!("type_identifier"),
             TokenType::Character(..) => return identifier!("character"),
             TokenType::String(..) => return identifier!("string"),
             TokenType::Integer(..) => return identifier!("integer"),
             TokenType::Float(..) => return identifier!("float"),
+            TokenType::Indent => return identifier!("indent"),
+            TokenType::Dedent => return identifier!("dedent"),
+            TokenType::Newline => return identifier!("newline"),
+            TokenType::StringPart(..) => return identifier!("string_part"),
+            TokenType::InterpolationStart => return identifier!("interpolation_start"),
+            TokenType::InterpolationEnd => return identifier!("interpolation_end"),
+            TokenType::Custom(name, ..) => return Data::Identifier(format_shared!("custom:{}", name)),
             TokenType::Invalid(..) => panic!(),
             TokenType::Ignored => panic!(),
         }
     }
+
+    // contextual keywords may start templates expecting the keyword as well as templates expecting an identifier
+    pub fn to_locations(&self) -> Vec<Data> {
+        match &self.token_type {
+            TokenType::Contextual(_, keyword) => return vec![Data::Identifier(format_shared!("keyword:{}", keyword)), identifier!("identifier")],
+            _other => return vec![self.to_location()],
+        }
+    }
 }
@@ -124,6 +124,7 @@ impl<T: Clone> SharedVector<T> {
     }
 
     pub fn iter_mut(&mut self) -> MutableVectorIterator<T> {
+        self.single_reference();
         return MutableVectorIterator::new(self);
     }
 
@@ -132,9 +133,15 @@ impl<T: Clone> SharedVector<T> {
     }
 
     pub fn reverse_iter_mut(&mut self) -> ReverseMutableVectorIterator<T> {
+        self.single_reference();
         return ReverseMutableVectorIterator::new(self);
     }
 
+    // vectors sharing the same storage have the same identity
+    pub fn identity(&self) -> usize {
+        return &*self.vector as *const Mutex<Vec<T>> as usize;
+    }
+
     pub fn transfer(&mut self) -> Self {
         let cloned = self.clone();
         self.clear();
@@ -55,20 +55,87 @@ macro_rules! token_matches_piece {
     });
 }
 
-pub fn parse(compiler: &Data, token_stream: &Vec<Token>, variant_registry: &VariantRegistry) -> Status<Data> {
+macro_rules! contextual_matches_piece {
+    ($type:ident, $token_stream:expr, $index:expr, $filters:expr, $keyword:expr) => ({
+        if $token_stream.len() > $index {
+            if let TokenType::Contextual(identifier, keyword) = &$token_stream[$index].token_type {
+                let data = match $keyword {
+                    true => keyword,
+                    false => identifier,
+                };
+
+                if $filters.is_empty() {
+                    let path = Path::new(SharedVector::new(), $index, 1, true, None);
+                    return MatchResult::Matched(vector![path]);
+                } else {
+                    if let Some(filter_index) = $filters.iter().position(|filter| filter == data) {
+                        let path = Path::new(vector![Decision::Filter(filter_index)], $index, 1, true, None);
+                        return MatchResult::Matched(vector![path]);
+                    }
+                }
+            }
+        }
+        token_matches_piece!($type, $token_stream, $index, $filters)
+    });
+}
+
+macro_rules! marker_matches_piece {
+    ($type:ident, $token_stream:expr, $index:expr) => ({
+        if $token_stream.len() > $index {
+            if let TokenType::$type = &$token_stream[$index].token_type {
+                let path = Path::new(SharedVector::new(), $index, 1, true, None);
+                return MatchResult::Matched(vector![path]);
+            }
+        }
+        MatchResult::Missed
+    });
+}
+
+macro_rules! custom_matches_piece {
+    ($token_stream:expr, $index:expr, $filters:expr) => ({
+        if $token_stream.len() > $index {
+            if let TokenType::Custom(name, _) = &$token_stream[$index].token_type {
+                if $filters.is_empty() {
+                    let path = Path::new(SharedVector::new(), $index, 1, true, None);
+                    return MatchResult::Matched(vector![path]);
+                } else {
+                    if let Some(filter_index) = $filters.iter().position(|filter| filter == name) {
+                        let path = Path::new(vector![Decision::Filter(filter_index)], $index, 1, true, None);
+                        return MatchResult::Matched(vector![path]);
+                    }
+                }
+            }
+        }
+        MatchResult::Missed
+    });
+}
+
+pub fn parse(compiler: &Data, token_stream: &Vec<Token>, variant_registry: &VariantRegistry, build: &Data) -> Status<Data> {
     let parseable_token_stream = token_stream.iter().filter(|token| token.parsable()).cloned().collect();
     let parser = confirm!(Parser::new(compiler, variant_registry, &parseable_token_stream));
     let (decision_stream, templates) = confirm!(parser.parse());
 
-    let mut template_builder = TemplateBuilder::new(token_stream, &decision_stream, &templates);
+    let mut template_builder = TemplateBuilder::new(token_stream, &decision_stream, &templates, compiler, build);
     let (raw_module, _positions) = confirm!(template_builder.build());
     return success!(raw_module);
 }
 
-pub fn call_parse(compiler: &Data, token_stream: &Data, variant_registry: &Data) -> Status<Data> {
+pub fn call_parse(compiler: &Data, token_stream: &Data, variant_registry: &Data, build: &Data) -> Status<Data> {
+    let _session = SourceMap::session();
+    let source = match confirm!(token_stream.index(&identifier!("file"))) {
+        Some(Data::String(file)) => file,
+        _other => format_shared!("string"),
+    };
+
+    // parse errors are reported like invalid tokens
+    let parsed = parse_serialized(compiler, token_stream, variant_registry, build);
+    return Diagnostic::report(parsed, compiler, build, format_shared!("failed to parse {}", source));
+}
+
+fn parse_serialized(compiler: &Data, token_stream: &Data, variant_registry: &Data, build: &Data) -> Status<Data> {
     let variant_registry = confirm!(VariantRegistry::deserialize(variant_registry));
-    let token_stream = confirm!(deserialize_token_stream(token_stream));
-    return parse(compiler, &token_stream, &variant_registry);
+    let deserialized = confirm!(deserialize_token_stream(token_stream));
+    return parse(compiler, &deserialized, &variant_registry, build);
 }
 
 pub fn deserialize_token_stream(serialized: &Data) -> Status<Vec<Token>> {
@@ -81,12 +148,13 @@ pub fn deserialize_token_stream(serialized: &Data) -> Status<Vec<Token>> {
     let source = expect!(source, string!("token stream may not miss the source field"));
     let source = unpack_string!(&source);
 
+    let file = SourceMap::register(&file, &source);
     let tokens = confirm!(serialized.index(&identifier!("tokens")));
     let tokens = expect!(tokens, string!("token stream may not miss the tokens field"));
 
     let mut token_stream = Vec::new();
     for token in unpack_list!(&tokens).iter() {
-        let token = confirm!(Token::deserialize(token, &file, &source));
+        let token = confirm!(Token::deserialize(token, file));
         token_stream.push(token);
     }
 
@@ -164,6 +232,10 @@ impl<'p> Parser<'p> {
             dependencies.insert(Data::Identifier(format_shared!("keyword:{}", keyword)), Vec::new());
         }
 
+        for pattern in variant_registry.avalible_patterns().iter() {
+            dependencies.insert(Data::Identifier(format_shared!("custom:{}", pattern)), Vec::new());
+        }
+
         if variant_registry.has_identifiers() {
             dependencies.insert(identifier!("identifier"), Vec::new());
         }
@@ -172,6 +244,18 @@ impl<'p> Parser<'p> {
             dependencies.insert(identifier!("type_identifier"), Vec::new());
         }
 
+        if variant_registry.has_layout {
+            dependencies.insert(identifier!("indent"), Vec::new());
+            dependencies.insert(identifier!("dedent"), Vec::new());
+            dependencies.insert(identifier!("newline"), Vec::new());
+        }
+
+        if variant_registry.has_interpolation {
+            dependencies.insert(identifier!("string_part"), Vec::new());
+            dependencies.insert(identifier!("interpolation_start"), Vec::new());
+            dependencies.insert(identifier!("interpolation_end"), Vec::new());
+        }
+
         if variant_registry.has_characters {
             dependencies.insert(identifier!("character"), Vec::new());
         }
@@ -356,11 +440,24 @@ impl<'p> Parser<'p> {
             return result.clone();
         }
 
+        // an assertion leading back to the same template at the same token does not match
+        processed[index].insert(destination.clone(), MatchResult::Missed);
+
         let mut found_paths = SharedVector::new();
         if self.token_stream.len() > index {
             let destination_pool = self.token_pool.get(destination).unwrap();
-            let relevant_pool = destination_pool.get(&self.token_stream[index].to_location()).unwrap();
-            self.find(destination, relevant_pool, index, None, &mut found_paths, processed);
+            let locations = self.token_stream[index].to_locations();
+            let mut relevant_pool = destination_pool.get(&locations[0]).unwrap().clone();
+            for location in locations.iter().skip(1) {
+                if let Some(pool) = destination_pool.get(location) {
+                    for dependency in pool.iter() {
+                        if !relevant_pool.contains(dependency) {
+                            relevant_pool.push(dependency.clone());
+                        }
+                    }
+                }
+            }
+            self.find(destination, &relevant_pool, index, None, &mut found_paths, processed);
         } else {
             let result = self.create_widthless(destination, index);
             result.update(&mut found_paths);
@@ -386,16 +483,28 @@ impl<'p> Parser<'p> {
             Piece::Comment(..) => panic!("comment may not be matched"),
             Piece::Template(_, filters) => return self.filtered_paths_from_token(filters, follow, index, processed),
             Piece::Merge(filters) => return self.filtered_paths_from_token(filters, follow, index, processed),
-            Piece::List(_, part, separator) => return self.list_from_token(part, separator, false, follow, index, processed),
-            Piece::Confirmed(_, part, separator) => return self.list_from_token(part, separator, true, follow, index, processed),
-            Piece::Keyword(_, filters) => return token_matches_piece!(Keyword, &self.token_stream, index, filters),
+            Piece::List(_, part, separator) => return self.list_from_token(part, separator, 1, None, follow, index, processed),
+            Piece::Confirmed(_, part, separator) => return self.list_from_token(part, separator, 2, None, follow, index, processed),
+            Piece::Repeat(_, part, separator, minimum, maximum) => return self.list_from_token(part, separator, *minimum, *maximum, follow, index, processed),
+            Piece::Optional(_, part) => return self.optional_from_token(part, follow, index, processed),
+            Piece::Keyword(_, filters) => return contextual_matches_piece!(Keyword, &self.token_stream, index, filters, true),
             Piece::Operator(_, filters) => return token_matches_piece!(Operator, &self.token_stream, index, filters),
-            Piece::Identifier(_, filters) => return token_matches_piece!(Identifier, &self.token_stream, index, filters),
+            Piece::Identifier(_, filters) => return contextual_matches_piece!(Identifier, &self.token_stream, index, filters, false),
             Piece::TypeIdentifier(_, filters) => return token_matches_piece!(TypeIdentifier, &self.token_stream, index, filters),
             Piece::String(_, filters) => return token_matches_piece!(String, &self.token_stream, index, filters),
             Piece::Character(_, filters) => return token_matches_piece!(Character, &self.token_stream, index, filters),
             Piece::Integer(_, filters) => return token_matches_piece!(Integer, &self.token_stream, index, filters),
             Piece::Float(_, filters) => return token_matches_piece!(Float, &self.token_stream, index, filters),
+            Piece::Indent(..) => return marker_matches_piece!(Indent, &self.token_stream, index),
+            Piece::Dedent(..) => return marker_matches_piece!(Dedent, &self.token_stream, index),
+            Piece::Newline(..) => return marker_matches_piece!(Newline, &self.token_stream, index),
+            Piece::StringPart(_, filters) => return token_matches_piece!(StringPart, &self.token_stream, index, filters),
+            Piece::InterpolationStart(..) => return marker_matches_piece!(InterpolationStart, &self.token_stream, index),
+            Piece::InterpolationEnd(..) => return marker_matches_piece!(InterpolationEnd, &self.token_stream, index),
+            Piece::Custom(_, filters) => return custom_matches_piece!(&self.token_stream, index, filters),
+            Piece::Precedence(precedence) => return self.precedence_from_token(precedence, follow, index, processed),
+            Piece::Lookahead(part) => return self.assertion_from_token(part, true, index, processed),
+            Piece::Not(part) => return self.assertion_from_token(part, false, index, processed),
         }
     }
 
@@ -405,8 +514,13 @@ impl<'p> Parser<'p> {
             Piece::Comment(..) => panic!("comment may not be matched"),
             Piece::Template(_, filters) => return template_matches_piece!(leading_template, leading_paths, filters, self),
             Piece::Merge(filters) => return template_matches_piece!(leading_template, leading_paths, filters, self),
-            Piece::List(_, part, separator) => return self.list_from_template(part, separator, false, leading_template, leading_paths, processed),
-            Piece::Confirmed(_, part, separator) => return self.list_from_template(part, separator, true, leading_template, leading_paths, processed),
+            Piece::List(_, part, separator) => return self.list_from_template(part, separator, 1, None, leading_template, leading_paths, processed),
+            Piece::Confirmed(_, part, separator) => return self.list_from_template(part, separator, 2, None, leading_template, leading_paths, processed),
+            Piece::Repeat(_, part, separator, minimum, maximum) => return self.list_from_template(part, separator, *minimum, *maximum, leading_template, leading_paths, processed),
+            Piece::Optional(_, part) => return self.optional_from_template(part, leading_template, leading_paths, processed),
+            Piece::Precedence(precedence) => return self.precedence_from_template(precedence, leading_template, leading_paths, processed),
+            Piece::Lookahead(part) => return self.assertion_from_token(part, true, leading_paths[0].index, processed),
+            Piece::Not(part) => return self.assertion_from_token(part, false, leading_paths[0].index, processed),
             _piece => return MatchResult::Missed,
         }
     }
@@ -447,6 +561,19 @@ impl<'p> Parser<'p> {
         return MatchResult::from(paths);
     }
 
+    // templates inside an assertion are always followed, even at the start of a flavor, since the assertion guards the token at index
+    fn assertion_from_token(&self, part: &Piece, expected: bool, index: usize, processed: &mut Processed) -> MatchResult {
+        let matched = match self.match_piece_from_token(part, true, index, processed) {
+            MatchResult::Matched(..) => true,
+            MatchResult::Missed => false,
+        };
+
+        if matched == expected {
+            return MatchResult::Matched(vector![Path::new(SharedVector::new(), index, 0, false, None)]);
+        }
+        return MatchResult::Missed;
+    }
+
     fn create_widthless(&self, location: &Data, index: usize) -> MatchResult {
         let template = self.templates.get(location).unwrap();
 
@@ -461,57 +588,191 @@ impl<'p> Parser<'p> {
         return MatchResult::Missed;
     }
 
-    fn list_from_token(&self, part: &Piece, separator: &Option<Piece>, confirmed: bool, follow: bool, index: usize, processed: &mut Processed) -> MatchResult {
+    // lists that may be empty mark every item list with next, so an empty list can not be confused with a single item without decisions
+    fn finish_list(mut found_paths: SharedVector<Path>, minimum: usize, index: usize) -> MatchResult {
+        if minimum == 0 {
+            Parser::inject_decision(&mut found_paths, Decision::Next);
+        }
+
+        Parser::inject_decision(&mut found_paths, Decision::List);
+        Parser::push_decision(&mut found_paths, Decision::End);
+
+        if minimum == 0 {
+            found_paths.push(Path::new(vector![Decision::List, Decision::End], index, 0, false, None));
+        }
+        return MatchResult::from(found_paths);
+    }
+
+    fn list_from_token(&self, part: &Piece, separator: &Option<Piece>, minimum: usize, maximum: Option<usize>, follow: bool, index: usize, processed: &mut Processed) -> MatchResult {
         let mut active_paths = vector![Path::new(SharedVector::new(), index, 0, follow, None)];
         let mut found_paths = SharedVector::new();
         let mut counter = 0;
 
         while !active_paths.is_empty() {
             self.active_paths_from_token(part, &mut active_paths, processed);
+            counter += 1;
 
-            if !confirmed || counter != 0 {
+            if counter >= minimum {
                 for path in active_paths.iter() {
                     found_paths.push(path.clone());
                 }
             }
 
+            if maximum == Some(counter) {
+                break;
+            }
+
             Parser::push_decision(&mut active_paths, Decision::Next);
             if let Some(separator) = separator {
                 self.active_paths_from_token(separator, &mut active_paths, processed);
             }
-            counter += 1;
         }
 
         Parser::reduce_paths(&mut found_paths);
-        Parser::inject_decision(&mut found_paths, Decision::List);
-        Parser::push_decision(&mut found_paths, Decision::End);
-        return MatchResult::from(found_paths); // part
+        return Parser::finish_list(found_paths, minimum, index);
     }
 
-    fn list_from_template(&self, part: &Piece, separator: &Option<Piece>, confirmed: bool, leading_template: &Data, leading_paths: &SharedVector<Path>, processed: &mut Processed) -> MatchResult {
+    fn list_from_template(&self, part: &Piece, separator: &Option<Piece>, minimum: usize, maximum: Option<usize>, leading_template: &Data, leading_paths: &SharedVector<Path>, processed: &mut Processed) -> MatchResult {
         let mut active_paths = vector![Path::new(SharedVector::new(), leading_paths[0].index, 0, false, None)];
         let mut found_paths = SharedVector::new();
         let mut counter = 0;
 
         while !active_paths.is_empty() {
             self.active_paths_from_template(part, leading_template, leading_paths, &mut active_paths, processed);
+            counter += 1;
 
-            if !confirmed || counter != 0 {
+            if counter >= minimum {
                 active_paths.iter().cloned().for_each(|path| found_paths.push(path));
             }
 
+            if maximum == Some(counter) {
+                break;
+            }
+
             Parser::push_decision(&mut active_paths, Decision::Next);
 
             if let Some(separator) = separator {
                 self.active_paths_from_template(separator, leading_template, leading_paths, &mut active_paths, processed);
             }
-            counter += 1;
         };
 
         Parser::reduce_paths(&mut found_paths);
-        Parser::inject_decision(&mut found_paths, Decision::List);
-        Parser::push_decision(&mut found_paths, Decision::End);
-        return MatchResult::from(found_paths); // part
+        return Parser::finish_list(found_paths, minimum, leading_paths[0].index);
+    }
+
+    fn optional_paths(present: MatchResult, index: usize) -> MatchResult {
+        let mut found_paths = match present {
+            MatchResult::Matched(mut paths) => {
+                Parser::inject_decision(&mut paths, Decision::Next);
+                paths
+            }
+            MatchResult::Missed => SharedVector::new(),
+        };
+
+        found_paths.push(Path::new(vector![Decision::End], index, 0, false, None));
+        return MatchResult::from(found_paths);
+    }
+
+    fn optional_from_token(&self, part: &Piece, follow: bool, index: usize, processed: &mut Processed) -> MatchResult {
+        let present = self.match_piece_from_token(part, follow, index, processed);
+        return Parser::optional_paths(present, index);
+    }
+
+    fn optional_from_template(&self, part: &Piece, leading_template: &Data, leading_paths: &SharedVector<Path>, processed: &mut Processed) -> MatchResult {
+        let present = self.match_piece_from_template(part, leading_template, leading_paths, processed);
+        return Parser::optional_paths(present, leading_paths[0].index);
+    }
+
+    fn operator_at(&self, index: usize) -> Option<&SharedString> {
+        if self.token_stream.len() > index {
+            if let TokenType::Operator(operator) = &self.token_stream[index].token_type {
+                return Some(operator);
+            }
+        }
+        return None;
+    }
+
+    fn precedence_operands(&self, precedence: &Precedence, mut expecting_paths: SharedVector<Path>, processed: &mut Processed) -> SharedVector<Path> {
+        let mut operand_paths = SharedVector::new();
+
+        while !expecting_paths.is_empty() {
+            for path in expecting_paths.transfer().iter() {
+                let index = path.index + path.width;
+
+                if let Some(operator) = self.operator_at(index) {
+                    if precedence.prefix(operator).is_some() {
+                        let mut prefixed_path = path.clone();
+                        prefixed_path.decisions.push(Decision::Filter(0));
+                        prefixed_path.width += 1;
+                        prefixed_path.confirmed = true;
+                        expecting_paths.push(prefixed_path);
+                    }
+                }
+
+                let mut operand_path = path.clone();
+                operand_path.decisions.push(Decision::Filter(1));
+                if let MatchResult::Matched(new_paths) = self.filtered_paths_from_token(&precedence.operand, path.confirmed, index, processed) {
+                    operand_paths.append(&Parser::derive(&operand_path, &new_paths));
+                }
+            }
+        }
+
+        return operand_paths;
+    }
+
+    fn precedence_tail(&self, precedence: &Precedence, mut operand_paths: SharedVector<Path>, processed: &mut Processed) -> MatchResult {
+        let mut found_paths = SharedVector::new();
+
+        while !operand_paths.is_empty() {
+            let mut expecting_paths = SharedVector::new();
+
+            while !operand_paths.is_empty() {
+                for path in operand_paths.transfer().iter() {
+                    let index = path.index + path.width;
+
+                    if let Some(operator) = self.operator_at(index) {
+                        if precedence.postfix(operator).is_some() {
+                            let mut postfixed_path = path.clone();
+                            postfixed_path.decisions.push(Decision::Filter(0));
+                            postfixed_path.width += 1;
+                            operand_paths.push(postfixed_path);
+                        }
+
+                        if precedence.binary(operator).is_some() {
+                            let mut binary_path = path.clone();
+                            binary_path.decisions.push(Decision::Filter(1));
+                            binary_path.width += 1;
+                            expecting_paths.push(binary_path);
+                        }
+                    }
+
+                    let mut found_path = path.clone();
+                    found_path.decisions.push(Decision::Filter(2));
+                    found_paths.push(found_path);
+                }
+            }
+
+            operand_paths = self.precedence_operands(precedence, expecting_paths, processed);
+        }
+
+        Parser::reduce_paths(&mut found_paths);
+        return MatchResult::from(found_paths);
+    }
+
+    fn precedence_from_token(&self, precedence: &Precedence, follow: bool, index: usize, processed: &mut Processed) -> MatchResult {
+        let expecting_paths = vector![Path::new(SharedVector::new(), index, 0, follow, None)];
+        let operand_paths = self.precedence_operands(precedence, expecting_paths, processed);
+        return self.precedence_tail(precedence, operand_paths, processed);
+    }
+
+    fn precedence_from_template(&self, precedence: &Precedence, leading_template: &Data, leading_paths: &SharedVector<Path>, processed: &mut Processed) -> MatchResult {
+        if !precedence.operand.contains(leading_template) {
+            return MatchResult::Missed;
+        }
+
+        let operand_path = Path::new(vector![Decision::Filter(1)], leading_paths[0].index, 0, false, None);
+        let operand_paths = Parser::derive(&operand_path, leading_paths);
+        return self.precedence_tail(precedence, operand_paths, processed);
     }
 
     fn decision_stream(&self, result: MatchResult) -> Status<SharedVector<Decision>> {
@@ -1,12 +1,14 @@
 use internal::*;
 use debug::*;
 
-use super::{ Templates, Decision, Piece, Token };
+use super::{ Templates, Decision, Piece, Token, Precedence, OperatorRule, Associativity };
 
 macro_rules! find {
-    ($type:ident, $internal:ident, $self:expr) => ({
-        if let Decision::Filter(..) = $self.decision_stream[$self.decision_index] {
-            $self.decision_index += 1;
+    ($type:ident, $internal:ident, $self:expr, $filters:expr) => ({
+        if !$filters.is_empty() {
+            if let Decision::Filter(..) = $self.decision_stream[$self.decision_index] {
+                $self.decision_index += 1;
+            }
         }
         while !$self.token_stream[$self.token_index].parsable() {
             $self.token_index += 1;
@@ -20,39 +22,86 @@ macro_rules! find {
     });
 }
 
+macro_rules! find_contextual {
+    ($type:ident, $self:expr, $filters:expr, $keyword:expr) => ({
+        if !$filters.is_empty() {
+            if let Decision::Filter(..) = $self.decision_stream[$self.decision_index] {
+                $self.decision_index += 1;
+            }
+        }
+        while !$self.token_stream[$self.token_index].parsable() {
+            $self.token_index += 1;
+        }
+        let data = match &$self.token_stream[$self.token_index].token_type {
+            TokenType::$type(data) => data.clone(),
+            TokenType::Contextual(_, keyword) if $keyword => keyword.clone(),
+            TokenType::Contextual(identifier, _) => identifier.clone(),
+            _other => panic!(),
+        };
+        $self.token_index += 1;
+        (Data::Identifier(data), $self.token_stream[$self.token_index - 1].position.clone())
+    });
+}
+
+enum PrecedenceItem {
+    Operand(Data, Vec<Position>),
+    Prefix(OperatorRule, Vec<Position>),
+    Postfix(OperatorRule, Vec<Position>),
+    Binary(OperatorRule, Vec<Position>),
+}
+
 pub struct TemplateBuilder<'t> {
     pub token_stream:   &'t Vec<Token>,
     decision_stream:    &'t SharedVector<Decision>,
     templates:          &'t Templates,
+    root:               &'t Data,
+    build:              &'t Data,
     decision_index:     usize,
+    documented:         usize,
     pub token_index:    usize,
 }
 
 impl<'t> TemplateBuilder<'t> {
 
-    pub fn new(token_stream: &'t Vec<Token>, decision_stream: &'t SharedVector<Decision>, templates: &'t Templates) -> Self {
+    pub fn new(token_stream: &'t Vec<Token>, decision_stream: &'t SharedVector<Decision>, templates: &'t Templates, root: &'t Data, build: &'t Data) -> Self {
         Self {
             token_stream:       token_stream,
             decision_stream:    decision_stream,
             templates:          templates,
+            root:               root,
+            build:              build,
             decision_index:     0,
+            documented:         0,
             token_index:        0,
         }
     }
 
     pub fn build(&mut self) -> Status<(Data, Vec<Position>)> {
 
+        // documentation belongs to the outermost template starting after it, the root spans the whole source
+        let documentation = match self.decision_index {
+            0 => None,
+            _other => self.collect_documentation(),
+        };
+
+        let mut entry_map = DataMap::new();
+        let mut positions_map = DataMap::new();
+        if let Some((documentation, documentation_positions)) = documentation {
+            entry_map.insert(identifier!("documentation"), documentation);
+            positions_map.insert(identifier!("documentation"), list!(documentation_positions.iter().map(|position| position.serialize()).collect()));
+        }
+
         let mut data_map = DataMap::new();
-        data_map.insert(identifier!("entries"), map!());
-        data_map.insert(identifier!("positions"), map!());
+        data_map.insert(identifier!("entries"), map!(entry_map));
+        data_map.insert(identifier!("positions"), map!(positions_map));
         let mut map = map!(data_map);
 
         if let Decision::Filter(..) = self.decision_stream[self.decision_index] {
             self.decision_index += 1;
         }
 
-        let template = match self.decision_stream[self.decision_index] {
-            Decision::Template(ref template) => self.templates.get(template).unwrap(), // TODO
+        let (location, template) = match self.decision_stream[self.decision_index] {
+            Decision::Template(ref template) => (template, self.templates.get(template).unwrap()), // TODO
             _ => panic!("decision expected template"),
         };
 
@@ -69,7 +118,10 @@ impl<'t> TemplateBuilder<'t> {
         let mut template_positions = Vec::new();
 
         for piece in template.flavors[flavor].pieces.iter() {
-            let (key, (data, mut positions)) = confirm!(self.build_piece(piece));
+            let (key, (data, mut positions)) = match piece {
+                Piece::Precedence(precedence) => (None, confirm!(self.build_precedence(precedence, &template.passes))),
+                piece => confirm!(self.build_piece(piece)),
+            };
 
             if let Some(key) = key {
                 let entry = confirm!(map.index(&identifier!("entries"))).unwrap();
@@ -80,8 +132,17 @@ impl<'t> TemplateBuilder<'t> {
                 let entry = confirm!(map.index(&identifier!("positions"))).unwrap();
                 let new_entry = confirm!(entry.insert(&key, serialized_positions));
                 map = confirm!(map.overwrite(&identifier!("positions"), new_entry));
+
+                // suffixes are only present on nodes that contain typed literals
+                if let Some(suffix) = self.literal_suffix(piece) {
+                    let entry = confirm!(map.index(&identifier!("suffixes"))).unwrap_or(map!());
+                    let new_entry = confirm!(entry.insert(&key, suffix));
+                    map = confirm!(map.overwrite(&identifier!("suffixes"), new_entry));
+                }
             } else if let Piece::Merge(..) = piece {
                 map = confirm!(map.merge(&data));
+            } else if let Piece::Precedence(..) = piece {
+                map = confirm!(map.merge(&data));
             }
 
             template_positions.append(&mut positions);
@@ -89,7 +150,14 @@ impl<'t> TemplateBuilder<'t> {
 
         let self_position = Position::range(template_positions, true);
         let serialized_self_position = list!(self_position.iter().map(|position| position.serialize()).collect());
-        map = confirm!(map.overwrite(&identifier!("position"), serialized_self_position));
+        map = confirm!(map.overwrite(&identifier!("position"), serialized_self_position.clone()));
+
+        if let Some(action) = &template.action {
+            let parameters = vector![map, serialized_self_position, location.clone()];
+            let last = confirm!(function(action, parameters, &None, self.root, self.build), Tag, action.clone());
+            map = expect!(last, string!("action didnt return a value"));
+        }
+
         return success!((map, self_position));
     }
 
@@ -98,9 +166,12 @@ impl<'t> TemplateBuilder<'t> {
         let mut comment_positions = Vec::new();
 
         while !self.token_stream[self.token_index].parsable() {
-            if let TokenType::Comment(data) = &self.token_stream[self.token_index].token_type {
-                comment_positions.extend_from_slice(&&self.token_stream[self.token_index].position[..]); // MAKE THIS BETTER AND FASTER
-                comment.push_str(data);
+            match &self.token_stream[self.token_index].token_type {
+                TokenType::Comment(data) | TokenType::Documentation(data) => {
+                    comment_positions.extend_from_slice(&&self.token_stream[self.token_index].position[..]); // MAKE THIS BETTER AND FASTER
+                    comment.push_str(data);
+                },
+                _other => {},
             }
             self.token_index += 1;
         }
@@ -108,7 +179,43 @@ impl<'t> TemplateBuilder<'t> {
         return (Data::String(comment), Position::range(comment_positions, true));
     }
 
-    fn build_list(&mut self, part: &Piece, separator: &Option<Piece>) -> Status<(Data, Vec<Position>)> {
+    // does not consume any tokens, so #comment pieces still see the documentation
+    fn collect_documentation(&mut self) -> Option<(Data, Vec<Position>)> {
+        let mut lines = Vec::new();
+        let mut documentation_positions = Vec::new();
+        let mut index = std::cmp::max(self.token_index, self.documented);
+
+        while index < self.token_stream.len() && !self.token_stream[index].parsable() {
+            if let TokenType::Documentation(data) = &self.token_stream[index].token_type {
+                documentation_positions.extend_from_slice(&self.token_stream[index].position[..]);
+                lines.push(data.clone());
+            }
+            index += 1;
+        }
+
+        self.documented = index;
+        if lines.is_empty() {
+            return None;
+        }
+
+        let mut documentation = SharedString::new();
+        for (line_index, line) in lines.iter().enumerate() {
+            if line_index > 0 {
+                documentation.push(Character::from_char('\n'));
+            }
+            documentation.push_str(line);
+        }
+        return Some((Data::String(documentation), Position::range(documentation_positions, true)));
+    }
+
+    fn literal_suffix(&self, piece: &Piece) -> Option<Data> {
+        match piece {
+            Piece::Integer(..) | Piece::Float(..) => return self.token_stream[self.token_index - 1].suffix.clone().map(Data::String),
+            _other => return None,
+        }
+    }
+
+    fn build_list(&mut self, part: &Piece, separator: &Option<Piece>, optional: bool) -> Status<(Data, Vec<Position>)> {
         let mut items = SharedVector::new();
         let mut list_positions = Vec::new();
 
@@ -118,6 +225,13 @@ impl<'t> TemplateBuilder<'t> {
             panic!("expected list decision");
         }
 
+        if optional {
+            self.decision_index += 1;
+            if let Decision::End = &self.decision_stream[self.decision_index - 1] {
+                return success!((list!(items), list_positions));
+            }
+        }
+
         loop {
             let (_, (part_data, part_positions)) = confirm!(self.build_piece(part));
             let mut data_map = DataMap::new();
@@ -127,6 +241,9 @@ impl<'t> TemplateBuilder<'t> {
             list_positions.extend_from_slice(&part_positions[..]); // MAKE THIS BETTER AND FASTER
             data_map.insert(identifier!("item"), part_data);
             positions_map.insert(identifier!("item"), serialized_part_positions);
+            if let Some(suffix) = self.literal_suffix(part) {
+                data_map.insert(identifier!("suffix"), suffix);
+            }
 
             if let Decision::End = &self.decision_stream[self.decision_index] {
                 self.decision_index += 1;
@@ -152,22 +269,215 @@ impl<'t> TemplateBuilder<'t> {
         return success!((list!(items), Position::range(list_positions, true)));
     }
 
+    fn build_optional(&mut self, key: &Option<Data>, part: &Piece) -> Status<(Option<Data>, (Data, Vec<Position>))> {
+        match self.decision_stream[self.decision_index] {
+
+            Decision::Next => {
+                self.decision_index += 1;
+                let (_, part) = confirm!(self.build_piece(part));
+                return success!((key.clone(), part));
+            }
+
+            Decision::End => {
+                self.decision_index += 1;
+                return success!((None, (identifier!("none"), Vec::new())));
+            }
+
+            _ => panic!("decision expected next or end"),
+        }
+    }
+
+    fn operator_token(&mut self) -> Status<(SharedString, Vec<Position>)> {
+        while !self.token_stream[self.token_index].parsable() {
+            self.token_index += 1;
+        }
+        if let TokenType::Operator(operator) = &self.token_stream[self.token_index].token_type {
+            self.token_index += 1;
+            return success!((operator.clone(), self.token_stream[self.token_index - 1].position.clone()));
+        }
+        return error!(string!("expected operator token"));
+    }
+
+    fn next_filter(&mut self) -> Status<usize> {
+        if let Decision::Filter(filter) = self.decision_stream[self.decision_index] {
+            self.decision_index += 1;
+            return success!(filter);
+        }
+        return error!(string!("decision expected filter"));
+    }
+
+    fn create_node(entries: Vec<(&str, Data, Vec<Position>)>, passes: &Option<Data>) -> (Data, Vec<Position>) {
+        let mut entry_map = DataMap::new();
+        let mut positions_map = DataMap::new();
+        let mut node_positions = Vec::new();
+
+        for (key, data, positions) in entries.into_iter() {
+            entry_map.insert(identifier!(key), data);
+            if !positions.is_empty() {
+                positions_map.insert(identifier!(key), list!(positions.iter().map(|position| position.serialize()).collect()));
+            }
+            node_positions.extend_from_slice(&positions[..]);
+        }
+
+        let node_positions = Position::range(node_positions, true);
+        let mut data_map = DataMap::new();
+        data_map.insert(identifier!("entries"), map!(entry_map));
+        data_map.insert(identifier!("positions"), map!(positions_map));
+        data_map.insert(identifier!("position"), list!(node_positions.iter().map(|position| position.serialize()).collect()));
+        if let Some(passes) = passes {
+            data_map.insert(keyword!("pass"), passes.clone());
+        }
+        return (map!(data_map), node_positions);
+    }
+
+    fn climb_unary(items: &mut Vec<PrecedenceItem>, passes: &Option<Data>) -> Status<(Data, Vec<Position>)> {
+        match items.remove(0) {
+
+            PrecedenceItem::Prefix(rule, operator_positions) => {
+                let (operand, operand_positions) = confirm!(TemplateBuilder::climb(items, rule.precedence, passes));
+                let entries = vec![("kind", identifier!("prefix"), Vec::new()), ("operator", Data::Identifier(rule.operator), operator_positions), ("operand", operand, operand_positions)];
+                return success!(TemplateBuilder::create_node(entries, passes));
+            }
+
+            PrecedenceItem::Operand(operand, operand_positions) => return success!((operand, operand_positions)),
+
+            _invalid => return error!(string!("expected operand or prefix operator")),
+        }
+    }
+
+    fn climb(items: &mut Vec<PrecedenceItem>, minimum: i64, passes: &Option<Data>) -> Status<(Data, Vec<Position>)> {
+        let (mut left, mut left_positions) = confirm!(TemplateBuilder::climb_unary(items, passes));
+
+        loop {
+            let rule = match items.first() {
+                Some(PrecedenceItem::Postfix(rule, _)) if rule.precedence >= minimum => rule.clone(),
+                Some(PrecedenceItem::Binary(rule, _)) if rule.precedence >= minimum => rule.clone(),
+                _other => break,
+            };
+
+            match items.remove(0) {
+
+                PrecedenceItem::Postfix(_, operator_positions) => {
+                    let entries = vec![("kind", identifier!("postfix"), Vec::new()), ("operand", left, left_positions), ("operator", Data::Identifier(rule.operator), operator_positions)];
+                    let (node, node_positions) = TemplateBuilder::create_node(entries, passes);
+                    left = node;
+                    left_positions = node_positions;
+                }
+
+                PrecedenceItem::Binary(_, operator_positions) => {
+                    let next_minimum = match rule.associativity == Associativity::Right {
+                        true => rule.precedence,
+                        false => rule.precedence + 1,
+                    };
+
+                    let (right, right_positions) = confirm!(TemplateBuilder::climb(items, next_minimum, passes));
+                    if rule.associativity == Associativity::None {
+                        if let Some(PrecedenceItem::Binary(next_rule, next_positions)) = items.first() {
+                            if next_rule.precedence == rule.precedence {
+                                let error = Error::Message(string!("operator {} is not associative", rule.operator));
+                                return error!(Positioned, error, list!(next_positions.iter().map(|position| position.serialize()).collect()));
+                            }
+                        }
+                    }
+
+                    let entries = vec![("kind", identifier!("binary"), Vec::new()), ("left", left, left_positions), ("operator", Data::Identifier(rule.operator), operator_positions), ("right", right, right_positions)];
+                    let (node, node_positions) = TemplateBuilder::create_node(entries, passes);
+                    left = node;
+                    left_positions = node_positions;
+                }
+
+                _invalid => return error!(string!("expected postfix or binary operator")),
+            }
+        }
+
+        return success!((left, left_positions));
+    }
+
+    fn build_precedence(&mut self, precedence: &Precedence, passes: &Option<Data>) -> Status<(Data, Vec<Position>)> {
+        let mut items = Vec::new();
+
+        loop {
+            while confirm!(self.next_filter()) == 0 {
+                let (operator, positions) = confirm!(self.operator_token());
+                let rule = expect!(precedence.prefix(&operator), string!("expected prefix operator"));
+                items.push(PrecedenceItem::Prefix(rule.clone(), positions));
+            }
+
+            let (operand, operand_positions) = confirm!(self.build());
+            items.push(PrecedenceItem::Operand(operand, operand_positions));
+
+            let mut filter = confirm!(self.next_filter());
+            while filter == 0 {
+                let (operator, positions) = confirm!(self.operator_token());
+                let rule = expect!(precedence.postfix(&operator), string!("expected postfix operator"));
+                items.push(PrecedenceItem::Postfix(rule.clone(), positions));
+                filter = confirm!(self.next_filter());
+            }
+
+            if filter == 2 {
+                break;
+            }
+
+            let (operator, positions) = confirm!(self.operator_token());
+            let rule = expect!(precedence.binary(&operator), string!("expected binary operator"));
+            items.push(PrecedenceItem::Binary(rule.clone(), positions));
+        }
+
+        return TemplateBuilder::climb(&mut items, i64::min_value(), passes);
+    }
+
+    fn marker_token(&mut self, name: &str) -> (Data, Vec<Position>) {
+        while !self.token_stream[self.token_index].parsable() {
+            self.token_index += 1;
+        }
+        self.token_index += 1;
+        return (identifier!(name), self.token_stream[self.token_index - 1].position.clone());
+    }
+
+    fn custom_token(&mut self, filters: &Vec<SharedString>) -> (Data, Vec<Position>) {
+        if !filters.is_empty() {
+            if let Decision::Filter(..) = self.decision_stream[self.decision_index] {
+                self.decision_index += 1;
+            }
+        }
+        while !self.token_stream[self.token_index].parsable() {
+            self.token_index += 1;
+        }
+        if let TokenType::Custom(_, text) = &self.token_stream[self.token_index].token_type {
+            self.token_index += 1;
+            return (Data::String(text.clone()), self.token_stream[self.token_index - 1].position.clone());
+        }
+        panic!("expected custom token");
+    }
+
     fn build_piece(&mut self, piece: &Piece) -> Status<(Option<Data>, (Data, Vec<Position>))> {
         match piece {
             Piece::Merge(_) => return success!((None, confirm!(self.build()))),
             Piece::Template(key, _) => return success!((key.clone(), confirm!(self.build()))),
             Piece::Comment(key) => return success!((Some(key.clone()), self.collect_comment())),
             Piece::Data(key, data) => return success!((Some(key.clone()), (data.clone(), Vec::new()))),
-            Piece::List(key, part, separator) => return success!((key.clone(), confirm!(self.build_list(part, separator)))),
-            Piece::Confirmed(key, part, separator) => return success!((key.clone(), confirm!(self.build_list(part, separator)))),
-            Piece::Keyword(key, _) => return success!((key.clone(), find!(Keyword, Identifier, self))),
-            Piece::Operator(key, _) => return success!((key.clone(), find!(Operator, Identifier, self))),
-            Piece::Identifier(key, _) => return success!((key.clone(), find!(Identifier, Identifier, self))),
-            Piece::TypeIdentifier(key, _) => return success!((key.clone(), find!(TypeIdentifier, Identifier, self))),
-            Piece::String(key, _) => return success!((key.clone(), find!(String, String, self))),
-            Piece::Character(key, _) => return success!((key.clone(), find!(Character, Character, self))),
-            Piece::Integer(key, _) => return success!((key.clone(), find!(Integer, Integer, self))),
-            Piece::Float(key, _) => return success!((key.clone(), find!(Float, Float, self))),
+            Piece::List(key, part, separator) => return success!((key.clone(), confirm!(self.build_list(part, separator, false)))),
+            Piece::Confirmed(key, part, separator) => return success!((key.clone(), confirm!(self.build_list(part, separator, false)))),
+            Piece::Repeat(key, part, separator, minimum, _) => return success!((key.clone(), confirm!(self.build_list(part, separator, *minimum == 0)))),
+            Piece::Optional(key, part) => return self.build_optional(key, part),
+            Piece::Keyword(key, filters) => return success!((key.clone(), find_contextual!(Keyword, self, filters, true))),
+            Piece::Operator(key, filters) => return success!((key.clone(), find!(Operator, Identifier, self, filters))),
+            Piece::Identifier(key, filters) => return success!((key.clone(), find_contextual!(Identifier, self, filters, false))),
+            Piece::TypeIdentifier(key, filters) => return success!((key.clone(), find!(TypeIdentifier, Identifier, self, filters))),
+            Piece::String(key, filters) => return success!((key.clone(), find!(String, String, self, filters))),
+            Piece::Character(key, filters) => return success!((key.clone(), find!(Character, Character, self, filters))),
+            Piece::Integer(key, filters) => return success!((key.clone(), find!(Integer, Integer, self, filters))),
+            Piece::Float(key, filters) => return success!((key.clone(), find!(Float, Float, self, filters))),
+            Piece::Indent(key) => return success!((key.clone(), self.marker_token("indent"))),
+            Piece::Dedent(key) => return success!((key.clone(), self.marker_token("dedent"))),
+            Piece::Newline(key) => return success!((key.clone(), self.marker_token("newline"))),
+            Piece::StringPart(key, filters) => return success!((key.clone(), find!(StringPart, String, self, filters))),
+            Piece::InterpolationStart(key) => return success!((key.clone(), self.marker_token("interpolation_start"))),
+            Piece::InterpolationEnd(key) => return success!((key.clone(), self.marker_token("interpolation_end"))),
+            Piece::Custom(key, filters) => return success!((key.clone(), self.custom_token(filters))),
+            Piece::Precedence(..) => panic!("precedence is built by its template"),
+            Piece::Lookahead(..) => return success!((None, (identifier!("none"), Vec::new()))),
+            Piece::Not(..) => return success!((None, (identifier!("none"), Vec::new()))),
         }
     }
 }
@@ -22,6 +22,9 @@ impl Flavor {
         for piece in self.pieces.iter() {
             confirm!(piece.validate(variant_registry, templates));
         }
+        if self.widthless == Some(true) {
+            ensure!(!self.pieces.iter().any(|piece| piece.is_assertion()), string!("flavor with a lookahead may not be widthless"));
+        }
         return success!(());
     }
 
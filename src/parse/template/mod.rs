@@ -1,6 +1,7 @@
 mod piece;
 mod flavor;
 mod builder;
+mod precedence;
 
 use internal::*;
 use debug::*;
@@ -8,12 +9,14 @@ use debug::*;
 pub use self::piece::Piece;
 pub use self::flavor::Flavor;
 pub use self::builder::TemplateBuilder;
+pub use self::precedence::{ Precedence, OperatorRule, Associativity };
 
 use parse::{ Decision, Templates, Dependencies };
 
 #[derive(Debug, Clone)]
 pub struct Template {
     pub passes:         Option<Data>,
+    pub action:         Option<Data>,
     pub flavors:        Vec<Flavor>,
     pub widthless:      Option<bool>,
     pub token_list:     Option<Vec<Data>>,
@@ -22,10 +25,11 @@ pub struct Template {
 
 impl Template {
 
-    pub fn new(flavors: Vec<Flavor>, passes: Option<Data>) -> Self {
+    pub fn new(flavors: Vec<Flavor>, passes: Option<Data>, action: Option<Data>) -> Self {
         Self {
             flavors:        flavors,
             passes:         passes,
+            action:         action,
             widthless:      None,
             token_list:     None,
             template_list:  None,
@@ -40,6 +44,7 @@ impl Template {
         let mut direct_dependencies = Vec::new();
         let mut flavors = Vec::new();
         let mut passes = None;
+        let mut action = None;
 
         if let Some(first) = template_stack.peek(0) {
             if first.is_map() {
@@ -48,6 +53,24 @@ impl Template {
             }
         }
 
+        if let Some(first) = template_stack.peek(0) {
+            if first == keyword!("action") {
+                template_stack.advance(1);
+                let function = expect!(template_stack.pop(), string!("expected action function"));
+                ensure!(function.is_path() || function.is_selector(), string!("action must be a function path; found {}", function.serialize()));
+                action = Some(function);
+            }
+        }
+
+        if let Some(first) = template_stack.peek(0) {
+            if first == keyword!("precedence") {
+                template_stack.advance(1);
+                let precedence_source = expect!(template_stack.pop(), string!("expected precedence table"));
+                let precedence = confirm!(Precedence::parse(&precedence_source, &mut direct_dependencies));
+                flavors.push(Flavor::new(vec![Piece::Precedence(Box::new(precedence))]));
+            }
+        }
+
         while let Some(flavor) = template_stack.pop() {
             let mut pieces = Vec::new();
             for piece in unpack_list!(&flavor).into_iter() {
@@ -58,7 +81,7 @@ impl Template {
         }
 
         ensure!(!flavors.is_empty(), string!("template {} does not have any flavors", location.serialize()));
-        templates.insert(location.clone(), Template::new(flavors, passes));
+        templates.insert(location.clone(), Template::new(flavors, passes, action));
 
         for dependency in &direct_dependencies {
             if templates.get(dependency).is_none() {
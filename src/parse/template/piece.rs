@@ -2,6 +2,7 @@ use internal::*;
 use debug::*;
 
 use parse::{ Decision, Templates };
+use super::{ Precedence, Associativity };
 
 macro_rules! filters {
     ($piece_stack:expr, $extractor:ident) => ({
@@ -52,6 +53,24 @@ macro_rules! typed_token_list { // TODO: clean up
         }
         true
     });
+    (Custom, $list:expr, $filters:expr, $registry:expr) => ({
+        if $filters.is_empty() {
+            for pattern in $registry.avalible_patterns().iter() {
+                let location = identifier!("custom:{}", pattern);
+                if !$list.contains(&location) {
+                    $list.push(location);
+                }
+            }
+        } else {
+            for pattern in $filters.iter() {
+                let location = identifier!("custom:{}", pattern);
+                if !$list.contains(&location) {
+                    $list.push(location);
+                }
+            }
+        }
+        true
+    });
 }
 
 #[derive(Debug, Clone)]
@@ -62,6 +81,8 @@ pub enum Piece {
     Template(Option<Data>, Vec<Data>),
     List(Option<Data>, Box<Piece>, Box<Option<Piece>>),
     Confirmed(Option<Data>, Box<Piece>, Box<Option<Piece>>),
+    Repeat(Option<Data>, Box<Piece>, Box<Option<Piece>>, usize, Option<usize>),
+    Optional(Option<Data>, Box<Piece>),
     Keyword(Option<Data>, Vec<SharedString>),
     Operator(Option<Data>, Vec<SharedString>),
     Identifier(Option<Data>, Vec<SharedString>),
@@ -70,6 +91,16 @@ pub enum Piece {
     Character(Option<Data>, Vec<Character>),
     Integer(Option<Data>, Vec<i64>),
     Float(Option<Data>, Vec<f64>),
+    Indent(Option<Data>),
+    Dedent(Option<Data>),
+    Newline(Option<Data>),
+    StringPart(Option<Data>, Vec<SharedString>),
+    InterpolationStart(Option<Data>),
+    InterpolationEnd(Option<Data>),
+    Custom(Option<Data>, Vec<SharedString>),
+    Precedence(Box<Precedence>),
+    Lookahead(Box<Piece>),
+    Not(Box<Piece>),
 }
 
 impl Piece {
@@ -83,6 +114,32 @@ impl Piece {
         return success!(());
     }
 
+    fn validate_repeat(part: &Piece, separator: &Option<Piece>, minimum: usize, maximum: Option<usize>, variant_registry: &VariantRegistry, templates: &Templates) -> Status<()> {
+        if let Some(maximum) = maximum {
+            ensure!(maximum != 0, string!("repeat maximum may not be zero"));
+            ensure!(minimum <= maximum, string!("repeat minimum {} is bigger than maximum {}", minimum, maximum));
+        }
+        return Piece::validate_list(part, separator, variant_registry, templates);
+    }
+
+    fn validate_optional(part: &Piece, variant_registry: &VariantRegistry, templates: &Templates) -> Status<()> {
+        confirm!(part.validate(variant_registry, templates));
+        ensure!(!part.calculate_widthless(templates).unwrap(), string!("part of an optional may not be widthless"));
+        return success!(());
+    }
+
+    fn validate_precedence(precedence: &Precedence, variant_registry: &VariantRegistry, templates: &Templates) -> Status<()> {
+        confirm!(variant_registry.validate_operators(&precedence.operator_names()));
+        ensure!(Piece::filter_widthless(&precedence.operand, templates) == Some(false), string!("precedence operand may not be widthless"));
+        return success!(());
+    }
+
+    fn validate_assertion(part: &Piece, variant_registry: &VariantRegistry, templates: &Templates) -> Status<()> {
+        confirm!(part.validate(variant_registry, templates));
+        ensure!(!part.calculate_widthless(templates).unwrap(), string!("part of a lookahead may not be widthless"));
+        return success!(());
+    }
+
     fn get_key(piece_stack: &mut DataStack, listed: bool, expected: bool) -> Status<Option<Data>> {
         if let Some(next) = piece_stack.peek(0) {
             if next.is_key() {
@@ -140,6 +197,52 @@ impl Piece {
                 return success!(Piece::Confirmed(key, Box::new(part), Box::new(separator)));
             }
 
+            "repeat" => {
+                let key = confirm!(Piece::get_key(&mut piece_stack, listed, false));
+                let part_source = expect!(piece_stack.pop(), string!("expected part"));
+                let part = confirm!(Piece::parse(&part_source, direct_dependencies, true));
+                let minimum = unpack_integer!(&expect!(piece_stack.pop(), string!("expected minimum")));
+                ensure!(minimum >= 0, string!("repeat minimum may not be negative"));
+                let maximum = match expect!(piece_stack.pop(), string!("expected maximum")) {
+                    ref none if *none == keyword!("none") => None,
+                    maximum => {
+                        let maximum = unpack_integer!(&maximum);
+                        ensure!(maximum >= 0, string!("repeat maximum may not be negative"));
+                        Some(maximum as usize)
+                    },
+                };
+                let separator = match piece_stack.pop() {
+                    Some(separator_source) => Some(confirm!(Piece::parse(&separator_source, direct_dependencies, true))),
+                    None => None,
+                };
+                confirm!(piece_stack.ensure_empty(), Tag, string!("repeat"));
+                return success!(Piece::Repeat(key, Box::new(part), Box::new(separator), minimum as usize, maximum));
+            }
+
+            "optional" => {
+                let key = confirm!(Piece::get_key(&mut piece_stack, listed, false));
+                let part_source = expect!(piece_stack.pop(), string!("expected part"));
+                let part = confirm!(Piece::parse(&part_source, direct_dependencies, true));
+                confirm!(piece_stack.ensure_empty(), Tag, string!("optional"));
+                return success!(Piece::Optional(key, Box::new(part)));
+            }
+
+            "lookahead" => {
+                ensure!(!listed, string!("lookahead may not be used in a list"));
+                let part_source = expect!(piece_stack.pop(), string!("expected part"));
+                let part = confirm!(Piece::parse(&part_source, direct_dependencies, true));
+                confirm!(piece_stack.ensure_empty(), Tag, string!("lookahead"));
+                return success!(Piece::Lookahead(Box::new(part)));
+            }
+
+            "not" => {
+                ensure!(!listed, string!("not may not be used in a list"));
+                let part_source = expect!(piece_stack.pop(), string!("expected part"));
+                let part = confirm!(Piece::parse(&part_source, direct_dependencies, true));
+                confirm!(piece_stack.ensure_empty(), Tag, string!("not"));
+                return success!(Piece::Not(Box::new(part)));
+            }
+
             "template" => {
                 let key = confirm!(Piece::get_key(&mut piece_stack, listed, false));
                 let filters = confirm!(Piece::template_filters(&mut piece_stack, direct_dependencies));
@@ -227,6 +330,50 @@ impl Piece {
                 return success!(Piece::Float(key, filters));
             }
 
+            "indent" => {
+                let key = confirm!(Piece::get_key(&mut piece_stack, listed, false));
+                confirm!(piece_stack.ensure_empty(), Tag, string!("indent"));
+                return success!(Piece::Indent(key));
+            }
+
+            "dedent" => {
+                let key = confirm!(Piece::get_key(&mut piece_stack, listed, false));
+                confirm!(piece_stack.ensure_empty(), Tag, string!("dedent"));
+                return success!(Piece::Dedent(key));
+            }
+
+            "newline" => {
+                let key = confirm!(Piece::get_key(&mut piece_stack, listed, false));
+                confirm!(piece_stack.ensure_empty(), Tag, string!("newline"));
+                return success!(Piece::Newline(key));
+            }
+
+            "string_part" => {
+                let key = confirm!(Piece::get_key(&mut piece_stack, listed, false));
+                let filters = filters!(&mut piece_stack, unpack_string);
+                confirm!(piece_stack.ensure_empty(), Tag, string!("string_part"));
+                return success!(Piece::StringPart(key, filters));
+            }
+
+            "interpolation_start" => {
+                let key = confirm!(Piece::get_key(&mut piece_stack, listed, false));
+                confirm!(piece_stack.ensure_empty(), Tag, string!("interpolation_start"));
+                return success!(Piece::InterpolationStart(key));
+            }
+
+            "interpolation_end" => {
+                let key = confirm!(Piece::get_key(&mut piece_stack, listed, false));
+                confirm!(piece_stack.ensure_empty(), Tag, string!("interpolation_end"));
+                return success!(Piece::InterpolationEnd(key));
+            }
+
+            "custom" => {
+                let key = confirm!(Piece::get_key(&mut piece_stack, listed, false));
+                let filters = filters!(&mut piece_stack, unpack_identifier);
+                confirm!(piece_stack.ensure_empty(), Tag, string!("custom"));
+                return success!(Piece::Custom(key, filters));
+            }
+
             invalid => return error!(string!("invalid template piece {}", invalid)),
         };
     }
@@ -258,13 +405,39 @@ impl Piece {
         return widthless;
     }
 
+    pub fn is_assertion(&self) -> bool {
+        match self {
+            Piece::Lookahead(..) => return true,
+            Piece::Not(..) => return true,
+            _piece => return false,
+        }
+    }
+
+    fn repeat_widthless(part: &Piece, separator: &Option<Piece>, minimum: usize, templates: &Templates) -> Option<bool> {
+        if minimum == 0 {
+            return Some(true);
+        }
+        let part_widthless = part.calculate_widthless(templates);
+        if minimum == 1 || part_widthless != Some(true) {
+            return part_widthless;
+        }
+        match separator {
+            Some(separator) => return separator.calculate_widthless(templates),
+            None => return Some(true),
+        }
+    }
+
     pub fn calculate_widthless(&self, templates: &Templates) -> Option<bool> {
         match self {
             Piece::List(_, part, _) => return part.calculate_widthless(templates),
+            Piece::Repeat(_, part, separator, minimum, _) => return Piece::repeat_widthless(part, separator, *minimum, templates),
+            Piece::Optional(..) => return Some(true),
             Piece::Template(_, filters) => return Piece::filter_widthless(filters, templates),
             Piece::Merge(filters) => return Piece::filter_widthless(filters, templates),
             Piece::Comment(..) => return Some(true),
             Piece::Data(..) => return Some(true),
+            Piece::Lookahead(..) => return Some(true),
+            Piece::Not(..) => return Some(true),
             _piece => return Some(false),
         }
     }
@@ -275,6 +448,8 @@ impl Piece {
             Piece::Comment(..) => return success!(()),
             Piece::List(_, part, separator) => return Piece::validate_list(part, separator, variant_registry, templates),
             Piece::Confirmed(_, part, separator) => return Piece::validate_list(part, separator, variant_registry, templates),
+            Piece::Repeat(_, part, separator, minimum, maximum) => return Piece::validate_repeat(part, separator, *minimum, *maximum, variant_registry, templates),
+            Piece::Optional(_, part) => return Piece::validate_optional(part, variant_registry, templates),
             Piece::Template(..) => return success!(()),
             Piece::Merge(..) => return success!(()),
             Piece::Keyword(_, filters) => return variant_registry.validate_keywords(filters),
@@ -285,6 +460,16 @@ impl Piece {
             Piece::Character(..) => return variant_registry.validate_characters(),
             Piece::Integer(_, filters) => return variant_registry.validate_integers(filters),
             Piece::Float(_, filters) => return variant_registry.validate_floats(filters),
+            Piece::Indent(..) => return variant_registry.validate_layout(),
+            Piece::Dedent(..) => return variant_registry.validate_layout(),
+            Piece::Newline(..) => return variant_registry.validate_layout(),
+            Piece::StringPart(..) => return variant_registry.validate_interpolation(),
+            Piece::InterpolationStart(..) => return variant_registry.validate_interpolation(),
+            Piece::InterpolationEnd(..) => return variant_registry.validate_interpolation(),
+            Piece::Custom(_, filters) => return variant_registry.validate_patterns(filters),
+            Piece::Precedence(precedence) => return Piece::validate_precedence(precedence, variant_registry, templates),
+            Piece::Lookahead(part) => return Piece::validate_assertion(part, variant_registry, templates),
+            Piece::Not(part) => return Piece::validate_assertion(part, variant_registry, templates),
         }
     }
 
@@ -326,14 +511,35 @@ impl Piece {
         return true;
     }
 
+    fn add_optional_list(part: &Piece, token_list: &mut Vec<Data>, template_list: &mut Vec<Data>, variant_registry: &VariantRegistry, templates: &Templates) -> bool {
+        part.generate_start_list(token_list, template_list, variant_registry, templates);
+        return false;
+    }
+
+    fn add_precedence_list(precedence: &Precedence, token_list: &mut Vec<Data>, template_list: &mut Vec<Data>, templates: &Templates) -> bool {
+        for rule in precedence.operators.iter() {
+            if rule.associativity == Associativity::Prefix {
+                let location = identifier!("operator:{}", rule.operator);
+                if !token_list.contains(&location) {
+                    token_list.push(location);
+                }
+            }
+        }
+        return Piece::add_template_list(template_list, &precedence.operand, templates);
+    }
+
     pub fn generate_start_list(&self, token_list: &mut Vec<Data>, template_list: &mut Vec<Data>, variant_registry: &VariantRegistry, templates: &Templates) -> bool {
         match self {
             Piece::Data(..) => return false,
             Piece::Comment(..) => return false,
+            Piece::Lookahead(..) => return false,
+            Piece::Not(..) => return false,
             Piece::Template(_, filters) => return Piece::add_template_list(template_list, filters, templates),
             Piece::Merge(filters) => return Piece::add_template_list(template_list, filters, templates),
             Piece::List(_, part, separator) => return Piece::add_list_list(false, part, separator, token_list, template_list, variant_registry, templates),
             Piece::Confirmed(_, part, separator) => return Piece::add_list_list(true, part, separator, token_list, template_list, variant_registry, templates),
+            Piece::Repeat(_, part, separator, minimum, _) => return Piece::add_list_list(*minimum > 1, part, separator, token_list, template_list, variant_registry, templates) && *minimum != 0,
+            Piece::Optional(_, part) => return Piece::add_optional_list(part, token_list, template_list, variant_registry, templates),
             Piece::Keyword(_, filters) => return typed_token_list!(Keyword, token_list, filters, variant_registry),
             Piece::Operator(_, filters) => return typed_token_list!(Operator, token_list, filters, variant_registry),
             Piece::Identifier(..) => return Piece::add_token_list(token_list, "identifier"),
@@ -342,6 +548,14 @@ impl Piece {
             Piece::Character(..) => return Piece::add_token_list(token_list, "character"),
             Piece::Integer(..) => return Piece::add_token_list(token_list, "integer"),
             Piece::Float(..) => return Piece::add_token_list(token_list, "float"),
+            Piece::Indent(..) => return Piece::add_token_list(token_list, "indent"),
+            Piece::Dedent(..) => return Piece::add_token_list(token_list, "dedent"),
+            Piece::Newline(..) => return Piece::add_token_list(token_list, "newline"),
+            Piece::StringPart(..) => return Piece::add_token_list(token_list, "string_part"),
+            Piece::InterpolationStart(..) => return Piece::add_token_list(token_list, "interpolation_start"),
+            Piece::InterpolationEnd(..) => return Piece::add_token_list(token_list, "interpolation_end"),
+            Piece::Custom(_, filters) => return typed_token_list!(Custom, token_list, filters, variant_registry),
+            Piece::Precedence(precedence) => return Piece::add_precedence_list(precedence, token_list, template_list, templates),
         }
     }
 
@@ -358,6 +572,20 @@ impl Piece {
         }
     }
 
+    fn create_widthless_repeat(part: &Piece, separator: &Option<Piece>, minimum: usize, decisions: &mut SharedVector<Decision>, templates: &Templates) {
+        decisions.push(Decision::List);
+        for index in 0..minimum {
+            if index != 0 {
+                decisions.push(Decision::Next);
+                if let Some(separator) = separator {
+                    separator.create_widthless(decisions, templates);
+                }
+            }
+            part.create_widthless(decisions, templates);
+        }
+        decisions.push(Decision::End);
+    }
+
     pub fn create_widthless(&self, decisions: &mut SharedVector<Decision>, templates: &Templates) {
         match self {
             Piece::Data(..) => return,
@@ -366,6 +594,8 @@ impl Piece {
             Piece::Merge(filters) => return Piece::create_widthless_filter(filters, decisions, templates),
             Piece::List(_, part, _) => part.create_widthless(decisions, templates),
             Piece::Confirmed(..) => panic!("this piece no widthless"),
+            Piece::Repeat(_, part, separator, minimum, _) => return Piece::create_widthless_repeat(part, separator, *minimum, decisions, templates),
+            Piece::Optional(..) => decisions.push(Decision::End),
             Piece::Keyword(..) => panic!("this piece no widthless"),
             Piece::Operator(..) => panic!("this piece no widthless"),
             Piece::Identifier(..) => panic!("this piece no widthless"),
@@ -374,6 +604,16 @@ impl Piece {
             Piece::Character(..) => panic!("this piece no widthless"),
             Piece::Integer(..) => panic!("this piece no widthless"),
             Piece::Float(..) => panic!("this piece no widthless"),
+            Piece::Indent(..) => panic!("this piece no widthless"),
+            Piece::Dedent(..) => panic!("this piece no widthless"),
+            Piece::Newline(..) => panic!("this piece no widthless"),
+            Piece::StringPart(..) => panic!("this piece no widthless"),
+            Piece::InterpolationStart(..) => panic!("this piece no widthless"),
+            Piece::InterpolationEnd(..) => panic!("this piece no widthless"),
+            Piece::Custom(..) => panic!("this piece no widthless"),
+            Piece::Precedence(..) => panic!("this piece no widthless"),
+            Piece::Lookahead(..) => panic!("this piece no widthless"),
+            Piece::Not(..) => panic!("this piece no widthless"),
         }
     }
 }
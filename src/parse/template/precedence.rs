@@ -0,0 +1,106 @@
+use internal::*;
+use debug::*;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Associativity {
+    Left,
+    Right,
+    None,
+    Prefix,
+    Postfix,
+}
+
+impl Associativity {
+
+    pub fn from(source: &str) -> Status<Self> {
+        match source {
+            "left" => return success!(Associativity::Left),
+            "right" => return success!(Associativity::Right),
+            "none" => return success!(Associativity::None),
+            "prefix" => return success!(Associativity::Prefix),
+            "postfix" => return success!(Associativity::Postfix),
+            invalid => return error!(string!("invalid associativity {}", invalid)),
+        }
+    }
+
+    pub fn is_binary(&self) -> bool {
+        match self {
+            Associativity::Prefix => return false,
+            Associativity::Postfix => return false,
+            _binary => return true,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct OperatorRule {
+    pub operator:       SharedString,
+    pub precedence:     i64,
+    pub associativity:  Associativity,
+}
+
+#[derive(Debug, Clone)]
+pub struct Precedence {
+    pub operand:        Vec<Data>,
+    pub operators:      Vec<OperatorRule>,
+}
+
+impl Precedence {
+
+    pub fn parse(source: &Data, direct_dependencies: &mut Vec<Data>) -> Status<Self> {
+        ensure!(source.is_map(), ExpectedFound, expected_list!["map"], source.clone());
+        let mut operand = Vec::new();
+        let mut operators = Vec::new();
+
+        for filter in unpack_list!(&index_field!(source, "operand")).into_iter() {
+            if !direct_dependencies.contains(&filter) {
+                direct_dependencies.push(filter.clone());
+            }
+            operand.push(filter);
+        }
+        ensure!(!operand.is_empty(), string!("precedence operand must have a filter"));
+
+        for rule in unpack_list!(&index_field!(source, "operators")).into_iter() {
+            let operator = unpack_identifier!(&index_field!(rule, "operator"));
+            let precedence = unpack_integer!(&index_field!(rule, "precedence"));
+            let associativity = confirm!(Associativity::from(&unpack_keyword!(&index_field!(rule, "associativity")).printable()));
+
+            let duplicate = operators.iter().any(|other: &OperatorRule| other.operator == operator && other.associativity.is_binary() == associativity.is_binary() && (associativity.is_binary() || other.associativity == associativity));
+            ensure!(!duplicate, string!("operator {} is declared twice", operator));
+
+            operators.push(OperatorRule {
+                operator:       operator,
+                precedence:     precedence,
+                associativity:  associativity,
+            });
+        }
+        ensure!(!operators.is_empty(), string!("precedence template needs at least one operator"));
+
+        return success!(Self {
+            operand:        operand,
+            operators:      operators,
+        });
+    }
+
+    pub fn operator_names(&self) -> Vec<SharedString> {
+        let mut names = Vec::new();
+        for rule in self.operators.iter() {
+            if !names.contains(&rule.operator) {
+                names.push(rule.operator.clone());
+            }
+        }
+        return names;
+    }
+
+    pub fn prefix(&self, operator: &SharedString) -> Option<&OperatorRule> {
+        return self.operators.iter().find(|rule| rule.operator == *operator && rule.associativity == Associativity::Prefix);
+    }
+
+    pub fn postfix(&self, operator: &SharedString) -> Option<&OperatorRule> {
+        return self.operators.iter().find(|rule| rule.operator == *operator && rule.associativity == Associativity::Postfix);
+    }
+
+    pub fn binary(&self, operator: &SharedString) -> Option<&OperatorRule> {
+        return self.operators.iter().find(|rule| rule.operator == *operator && rule.associativity.is_binary());
+    }
+}
@@ -0,0 +1,165 @@
+use internal::*;
+use debug::*;
+
+use super::Tokenizer;
+
+fn starts_with(source: &SharedString, prefix: &SharedString) -> bool {
+    return source.find(prefix) == Some(0);
+}
+
+fn conflict(kind: &str, message: SharedString) -> Diagnostic {
+    let mut diagnostic = Diagnostic::new("warning", message);
+    diagnostic.kind = SharedString::from(kind);
+    return diagnostic;
+}
+
+impl Tokenizer {
+
+    // signatures that are identical are rejected while registering, this finds the overlaps that depend on the order of the partials
+    pub fn conflicts(&self) -> Vec<Diagnostic> {
+        let mut conflicts = self.partial_conflicts();
+
+        for (name, mode) in self.modes.iter() {
+            for mut diagnostic in mode.partial_conflicts().into_iter() {
+                diagnostic.tags.push(format_shared!("mode {}", name));
+                conflicts.push(diagnostic);
+            }
+        }
+        return conflicts;
+    }
+
+    fn partial_conflicts(&self) -> Vec<Diagnostic> {
+        let mut conflicts = Vec::new();
+
+        if let Some(operator_tokenizer) = &self.operator_tokenizer {
+            let operators = operator_tokenizer.operators();
+
+            // numbers are checked before operators, so the operator only applies if no digits follow
+            if let Some(number_tokenizer) = &self.number_tokenizer {
+                for signature in number_tokenizer.signatures().iter() {
+                    for operator in operators.iter() {
+                        if starts_with(operator, signature) {
+                            conflicts.push(conflict("ambiguous_number", format_shared!("operator {} starts with the number prefix {}; the number tokenizer claims it if a number follows", operator.serialize(), signature.serialize())));
+                        } else if starts_with(signature, operator) {
+                            conflicts.push(conflict("ambiguous_number", format_shared!("number prefix {} starts with the operator {}; the number tokenizer claims it if a number follows", signature.serialize(), operator.serialize())));
+                        }
+                    }
+                }
+            }
+
+            // comments are checked first, so an operator starting with a comment delimiter is never produced
+            if let Some(comment_tokenizer) = &self.comment_tokenizer {
+                for delimiter in comment_tokenizer.delimiters().iter() {
+                    for operator in operators.iter() {
+                        if starts_with(operator, delimiter) {
+                            conflicts.push(conflict("shadowed_operator", format_shared!("operator {} starts with the comment delimiter {} and is always read as a comment", operator.serialize(), delimiter.serialize())));
+                        }
+                    }
+                }
+            }
+        }
+
+        // strings and characters are checked after comments but before operators
+        let mut delimiters = Vec::new();
+        if let Some(character_tokenizer) = &self.character_tokenizer {
+            delimiters.extend(character_tokenizer.delimiters().into_iter().map(|delimiter| ("character", delimiter)));
+        }
+        if let Some(string_tokenizer) = &self.string_tokenizer {
+            delimiters.extend(string_tokenizer.delimiters().into_iter().map(|delimiter| ("string", delimiter)));
+        }
+
+        for (kind, delimiter) in delimiters.iter() {
+            if let Some(comment_tokenizer) = &self.comment_tokenizer {
+                for comment_delimiter in comment_tokenizer.delimiters().iter() {
+                    if starts_with(delimiter, comment_delimiter) {
+                        conflicts.push(conflict("shadowed_string", format_shared!("{} delimiter {} starts with the comment delimiter {} and is always read as a comment", kind, delimiter.serialize(), comment_delimiter.serialize())));
+                    }
+                }
+            }
+
+            if let Some(operator_tokenizer) = &self.operator_tokenizer {
+                for operator in operator_tokenizer.operators().iter() {
+                    if starts_with(operator, delimiter) {
+                        conflicts.push(conflict("shadowed_operator", format_shared!("operator {} starts with the {} delimiter {}; the {} tokenizer claims it first", operator.serialize(), kind, delimiter.serialize(), kind)));
+                    }
+                }
+            }
+        }
+
+        // the character tokenizer is checked first, so the string only applies if no character literal follows
+        if let (Some(character_tokenizer), Some(string_tokenizer)) = (&self.character_tokenizer, &self.string_tokenizer) {
+            for delimiter in string_tokenizer.delimiters().iter() {
+                for character_delimiter in character_tokenizer.delimiters().iter() {
+                    if starts_with(delimiter, character_delimiter) {
+                        conflicts.push(conflict("ambiguous_string", format_shared!("string delimiter {} starts with the character delimiter {}; the character tokenizer claims it if a character literal follows", delimiter.serialize(), character_delimiter.serialize())));
+                    }
+                }
+            }
+        }
+
+        if let (Some(keyword_tokenizer), Some(identifier_tokenizer)) = (&self.keyword_tokenizer, &self.identifier_tokenizer) {
+            for word in keyword_tokenizer.words().iter() {
+                if let Some(prefix) = identifier_tokenizer.prefix(word) {
+                    conflicts.push(conflict("shadowed_identifier", format_shared!("keyword {} starts with the identifier prefix {}; the keyword tokenizer claims it first", word, prefix)));
+                }
+            }
+        }
+
+        // invalid and ignored classes of the operator tokenizer are checked before keywords and identifiers
+        if let Some(operator_tokenizer) = &self.operator_tokenizer {
+            let classes = operator_tokenizer.classes();
+            let mut words = Vec::new();
+            if let Some(keyword_tokenizer) = &self.keyword_tokenizer {
+                words.extend(keyword_tokenizer.words().into_iter().map(|word| ("keyword", "shadowed_keyword", word)));
+            }
+            if let Some(identifier_tokenizer) = &self.identifier_tokenizer {
+                words.extend(identifier_tokenizer.prefixes().into_iter().map(|prefix| ("identifier prefix", "shadowed_identifier", prefix)));
+            }
+
+            for (name, kind, word) in words.into_iter() {
+                if CharacterClass::any_contains(&classes, word[0]) {
+                    conflicts.push(conflict(kind, format_shared!("{} {} starts with a character of the operator classes and is never read", name, word)));
+                }
+            }
+        }
+
+        // patterns are checked before every partial except comments
+        if let Some(pattern_tokenizer) = &self.pattern_tokenizer {
+            let mut literals = delimiters.iter().map(|(kind, delimiter)| (format!("{} delimiter", kind), delimiter.clone())).collect::<Vec<_>>();
+            if let Some(number_tokenizer) = &self.number_tokenizer {
+                literals.extend(number_tokenizer.signatures().into_iter().map(|signature| (String::from("number prefix"), signature)));
+            }
+            if let Some(operator_tokenizer) = &self.operator_tokenizer {
+                literals.extend(operator_tokenizer.operators().into_iter().map(|operator| (String::from("operator"), operator)));
+            }
+            if let Some(keyword_tokenizer) = &self.keyword_tokenizer {
+                literals.extend(keyword_tokenizer.words().into_iter().map(|word| (String::from("keyword"), word)));
+            }
+            if let Some(identifier_tokenizer) = &self.identifier_tokenizer {
+                literals.extend(identifier_tokenizer.prefixes().into_iter().map(|prefix| (String::from("identifier prefix"), prefix)));
+            }
+
+            for (name, literal) in literals.iter() {
+                if let Some(pattern) = pattern_tokenizer.claims(literal) {
+                    conflicts.push(conflict("shadowed_by_pattern", format_shared!("pattern {} matches the start of the {} {}; the pattern tokenizer claims it first", pattern, name, literal.serialize())));
+                }
+            }
+        }
+
+        // templates can not tell custom tokens with the same name apart
+        let mut custom: Vec<(&'static str, SharedString)> = Vec::new();
+        if let Some(pattern_tokenizer) = &self.pattern_tokenizer {
+            custom.extend(pattern_tokenizer.names().into_iter().map(|name| ("pattern", name)));
+        }
+        for partial in self.partials.iter() {
+            for name in partial.custom().into_iter() {
+                if let Some((owner, _)) = custom.iter().find(|(_, other)| *other == name) {
+                    conflicts.push(conflict("duplicate_custom", format_shared!("custom token {} is produced by the {} tokenizer and the {} tokenizer", name, owner, partial.name())));
+                }
+                custom.push((partial.name(), name));
+            }
+        }
+
+        return conflicts;
+    }
+}
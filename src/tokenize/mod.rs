@@ -1,10 +1,19 @@
 mod partial;
+mod analysis;
 
 use internal::*;
 use debug::*;
 
 use self::partial::*;
 
+pub use self::partial::TokenizerPartial;
+
+use std::collections::VecDeque;
+use std::rc::Rc;
+use std::io::{ Read, Cursor };
+
+static STREAMED_NOTES: usize = 1024;
+
 macro_rules! create {
     ($type:ident, $name:expr, $compiler:expr, $character_stack:expr, $variant_registry:expr) => (
         match guaranteed!($compiler.index(&keyword!("{}_tokenizer", $name))) {
@@ -15,65 +24,113 @@ macro_rules! create {
 }
 
 macro_rules! find {
-    ($tokenizer:expr, $($arguments:tt)*) => (
+    ($declined:expr, $name:expr, $tokenizer:expr, $($arguments:tt)*) => (
         if let Some(tokenizer) = $tokenizer {
             if confirm!(tokenizer.find($($arguments)*)) {
-                continue;
+                return success!($name);
             }
+            $declined.push($name);
         }
     );
 }
 
+fn debug_setting(root: &Data, name: &str) -> Status<bool> {
+    match confirm!(root.index(&path!(vector![keyword!("tokenizer_debug"), keyword!(name)]))) {
+        Some(enabled) => return success!(unpack_boolean!(&enabled)),
+        None => return success!(false),
+    }
+}
+
+// streams the source in chunks of the given number of bytes instead of tokenizing it at once
+fn debug_chunk_size(root: &Data) -> Status<Option<usize>> {
+    match confirm!(root.index(&path!(vector![keyword!("tokenizer_debug"), keyword!("stream")]))) {
+        Some(chunk_size) => {
+            let chunk_size = unpack_integer!(&chunk_size);
+            ensure!(chunk_size > 0, string!("stream chunk size must be positive"));
+            return success!(Some(chunk_size as usize));
+        }
+        None => return success!(None),
+    }
+}
+
 pub fn tokenize(compiler: &Data, source_string: SharedString, source_file: Option<SharedString>, complete: bool) -> Status<(Vec<Token>, VariantRegistry, Vec<Note>)> {
     let tokenizer = confirm!(Tokenizer::new(compiler));
     return tokenizer.tokenize(source_string, source_file, complete);
 }
 
+fn record_conflicts(compiler: &Data, build: &Data) -> Status<()> {
+    if confirm!(debug_setting(compiler, "conflicts")) {
+        for diagnostic in confirm!(Tokenizer::new(compiler)).conflicts().into_iter() {
+            confirm!(diagnostic.record(compiler, build));
+        }
+    }
+    return success!(());
+}
+
+pub fn diagnostics(token_stream: &Vec<Token>, notes: &Vec<Note>, root: &Data, build: &Data) -> Vec<Diagnostic> {
+    let mut diagnostics: Vec<Diagnostic> = token_stream.iter().filter_map(|token| Diagnostic::from_token(token, &Some(root), build)).collect();
+    diagnostics.extend(notes.iter().map(|note| Diagnostic::from_note(note)));
+    return diagnostics;
+}
+
 pub fn call_tokenize(compiler: &Data, source_string: &Data, source_file: &Data, complete: &Data, handle_invalid: &Data, build: &Data) -> Status<Data> {
     ensure!(source_file.is_string(), string!("source file must be a string"));
+    let _session = SourceMap::session();
 
-    let unpacked_source_string = unpack_string!(source_string);
     let unpacked_source_file = (*source_file != identifier!("none")).then_some(extract_string!(source_file));
+    let source = unpacked_source_file.clone().unwrap_or(format_shared!("string"));
+    let tokenized = tokenize_source(compiler, source_string, source_file, unpacked_source_file, complete, handle_invalid, build);
+    return Diagnostic::report(tokenized, compiler, build, format_shared!("failed to tokenize {}", source));
+}
+
+fn tokenize_source(compiler: &Data, source_string: &Data, source_file: &Data, unpacked_source_file: Option<SharedString>, complete: &Data, handle_invalid: &Data, build: &Data) -> Status<Data> {
+    let unpacked_source_string = unpack_string!(source_string);
     let complete = extract_boolean!(complete);
     let handle_invalid = extract_boolean!(handle_invalid);
+    confirm!(record_conflicts(compiler, build));
     let (token_stream, variant_registry, notes) = confirm!(tokenize(&compiler, unpacked_source_string, unpacked_source_file.clone(), complete));
 
+    let diagnostics = diagnostics(&token_stream, &notes, compiler, build);
+    let output_format = confirm!(Diagnostic::output_format(compiler));
+
     if handle_invalid {
+        let handler_function_path = path!(vector![keyword!("functions"), keyword!("tokenizer")]);
+        let handler_function = confirm!(compiler.index(&handler_function_path));
         let mut errors_found = false;
-        let source = match unpacked_source_file {
-            Some(file_name) => file_name,
-            None => format_shared!("string"),
-        };
 
         for token in token_stream.iter() {
             if let TokenType::Invalid(error) = &token.token_type {
-                let error_message = error.clone().display(&Some(compiler), build);
-                let position = token.serialize_position();
-
-                let handler_function_path = path!(vector![keyword!("functions"), keyword!("tokenizer")]);
-                let handler_function = match compiler.index(&handler_function_path) {
-                    Status::Success(handler_function) => handler_function,
-                    Status::Error(error) => panic!("index root failed: {}", error.display(&Some(compiler), build)),
-                };
-
                 if handler_function.is_some() {
+                    let error_message = error.clone().display(&Some(compiler), build);
+                    let position = list!(token.position.iter().map(|position| position.serialize()).collect());
                     if let Status::Error(error) = function(&keyword!("tokenizer"), vector![Data::String(error_message), position], &None, compiler, build) {
                         format_shared!("error in tokenizer handler: {}", error.display(&None, build));
                     }
-                } else {
-                    println!("in {} line {} row {}:", source, token.position[0].line, token.position[0].character);
-                    println!("{}", error_message);
                 }
-
                 errors_found = true;
             }
         }
 
+        // invalid tokens are always reported, notes only if the compiler asks for diagnostics
+        if handler_function.is_none() {
+            for diagnostic in diagnostics.iter() {
+                if diagnostic.severity.printable() == "error" || output_format.is_some() {
+                    confirm!(diagnostic.emit(output_format.unwrap_or(false)));
+                }
+            }
+        }
+
+        // the invalid tokens were already reported, so the error is not reported again
         if errors_found {
-            return error!(string!("failed to parse {}", source));
+            let source = unpacked_source_file.unwrap_or(format_shared!("string"));
+            return error!(Reported, Error::Message(string!("failed to parse {}", source)));
         }
     }
 
+    for diagnostic in diagnostics.into_iter().filter(|diagnostic| diagnostic.severity.printable() != "error") {
+        confirm!(diagnostic.record(compiler, build));
+    }
+
     let mut return_map = Map::new();
     return_map.insert(identifier!("token_stream"), serialize_token_stream(token_stream, source_string, source_file, compiler, build));
     return_map.insert(identifier!("registry"), variant_registry.serialize());
@@ -97,24 +154,73 @@ fn serialize_token_stream(token_stream: Vec<Token>, source_string: &Data, source
 
 
 pub struct Tokenizer {
+    layout_tokenizer: Option<LayoutTokenizer>,
     comment_tokenizer: Option<CommentTokenizer>,
+    pattern_tokenizer: Option<PatternTokenizer>,
+    partials: Vec<Rc<dyn TokenizerPartial>>,
     number_tokenizer: Option<NumberTokenizer>,
     string_tokenizer: Option<StringTokenizer>,
     character_tokenizer: Option<CharacterTokenizer>,
     operator_tokenizer: Option<OperatorTokenizer>,
     keyword_tokenizer: Option<KeywordTokenizer>,
     identifier_tokenizer: Option<IdentifierTokenizer>,
+    push_mode: Vec<(SharedString, SharedString)>,
+    pop_mode: Vec<SharedString>,
+    modes: Vec<(SharedString, Tokenizer)>,
     character_stack: CharacterStack,
     variant_registry: VariantRegistry,
+    explain: bool,
+    chunk_size: Option<usize>,
+}
+
+struct TokenizerState {
+    layout:             Layout,
+    modes:              Vec<usize>,
+    interpolations:     Vec<Interpolation>,
+}
+
+impl TokenizerState {
+
+    fn new() -> Self {
+        Self {
+            layout:             Layout::new(),
+            modes:              Vec::new(),
+            interpolations:     Vec::new(),
+        }
+    }
 }
 
 impl Tokenizer {
 
     pub fn new(compiler: &Data) -> Status<Self> {
+        let mut tokenizer = confirm!(Tokenizer::create(compiler, compiler));
+
+        if let Some(modes) = confirm!(compiler.index(&keyword!("modes"))) {
+            for (name, settings) in confirm!(modes.pairs()).into_iter() {
+                let name = unpack_keyword!(&name);
+                let mode = confirm!(Tokenizer::create(&settings, compiler), Tag, string!(String, name.clone()));
+                tokenizer.variant_registry.merge(&mode.variant_registry);
+                tokenizer.modes.push((name, mode));
+            }
+        }
+
+        for transitions in std::iter::once(&tokenizer.push_mode).chain(tokenizer.modes.iter().map(|(_, mode)| &mode.push_mode)) {
+            for (_operator, mode) in transitions.iter() {
+                ensure!(tokenizer.modes.iter().any(|(name, _)| name == mode), string!("undefined tokenizer mode {}", mode));
+            }
+        }
+
+        return success!(tokenizer);
+    }
+
+    fn create(compiler: &Data, root: &Data) -> Status<Self> {
+        ensure!(compiler.is_map(), ExpectedFound, expected_list!["map"], compiler.clone());
         let mut character_stack = CharacterStack::new(SharedString::new(), None);
         let mut variant_registry = VariantRegistry::new();
 
+        let layout_tokenizer = create!(LayoutTokenizer, "layout", &compiler, &mut character_stack, &mut variant_registry);
         let comment_tokenizer = create!(CommentTokenizer, "comment", &compiler, &mut character_stack, &mut variant_registry);
+        let pattern_tokenizer = create!(PatternTokenizer, "pattern", &compiler, &mut character_stack, &mut variant_registry);
         let number_tokenizer = create!(NumberTokenizer, "number", &compiler, &mut character_stack, &mut variant_registry);
         let string_tokenizer = create!(StringTokenizer, "string", &compiler, &mut character_stack, &mut variant_registry);
         let character_tokenizer = create!(CharacterTokenizer, "character", &compiler, &mut character_stack, &mut variant_registry);
@@ -122,42 +228,296 @@ impl Tokenizer {
         let keyword_tokenizer = create!(KeywordTokenizer, "keyword", &compiler, &mut character_stack, &mut variant_registry);
         let identifier_tokenizer = create!(IdentifierTokenizer, "identifier", &compiler, &mut character_stack, &mut variant_registry);
 
+        let mut partials: Vec<Rc<dyn TokenizerPartial>> = Vec::new();
+        if let Some(settings) = confirm!(compiler.index(&keyword!("function_tokenizer"))) {
+            partials.push(Rc::new(confirm!(FunctionTokenizer::new(&settings, root, &mut variant_registry))));
+        }
+
+        let mut push_mode = Vec::new();
+        if let Some(transitions) = confirm!(compiler.index(&keyword!("push_mode"))) {
+            for (operator, mode) in confirm!(transitions.pairs()).into_iter() {
+                let operator = unpack_identifier!(&operator);
+                ensure!(variant_registry.is_operator(&operator), string!("{} is not a valid operator", operator));
+                push_mode.push((operator, unpack_keyword!(&mode)));
+            }
+        }
+
+        let mut pop_mode = Vec::new();
+        if let Some(operators) = confirm!(compiler.index(&keyword!("pop_mode"))) {
+            for operator in unpack_list!(&operators).into_iter() {
+                let operator = unpack_identifier!(&operator);
+                ensure!(variant_registry.is_operator(&operator), string!("{} is not a valid operator", operator));
+                pop_mode.push(operator);
+            }
+        }
+
         return success!(Self {
+            layout_tokenizer: layout_tokenizer,
             comment_tokenizer: comment_tokenizer,
+            pattern_tokenizer: pattern_tokenizer,
+            partials: partials,
             number_tokenizer: number_tokenizer,
             string_tokenizer: string_tokenizer,
             character_tokenizer: character_tokenizer,
             operator_tokenizer: operator_tokenizer,
             keyword_tokenizer: keyword_tokenizer,
             identifier_tokenizer: identifier_tokenizer,
+            push_mode: push_mode,
+            pop_mode: pop_mode,
+            modes: Vec::new(),
             character_stack: character_stack,
             variant_registry: variant_registry,
+            explain: confirm!(debug_setting(root, "explain")),
+            chunk_size: confirm!(debug_chunk_size(root)),
         })
     }
 
+    // native partials are checked after the function tokenizer, in the order they were registered, in every mode
+    pub fn register_partial(&mut self, mut partial: Box<dyn TokenizerPartial>) -> Status<()> {
+        confirm!(partial.register(&mut self.character_stack, &mut self.variant_registry));
+        for (name, mode) in self.modes.iter_mut() {
+            confirm!(partial.register(&mut mode.character_stack, &mut mode.variant_registry), Tag, string!(String, name.clone()));
+            self.variant_registry.merge(&mode.variant_registry);
+        }
+
+        let partial: Rc<dyn TokenizerPartial> = Rc::from(partial);
+        for (_, mode) in self.modes.iter_mut() {
+            mode.partials.push(partial.clone());
+        }
+        self.partials.push(partial);
+        return success!(());
+    }
+
+    fn current(&self, modes: &Vec<usize>) -> &Tokenizer {
+        match modes.last() {
+            Some(index) => return &self.modes[*index].1,
+            None => return self,
+        }
+    }
+
+    fn next_tokens(&self, character_stack: &mut CharacterStack, token_stream: &mut Vec<Token>, notes: &mut Vec<Note>, state: &mut TokenizerState, complete: bool) -> Status<()> {
+        let tokenizer = self.current(&state.modes);
+        let length = token_stream.len();
+        confirm!(tokenizer.layout_tokens(character_stack, token_stream, notes, state, complete));
+
+        let mut changed = false;
+        for token in token_stream.iter().skip(length) {
+            if let TokenType::Operator(operator) = &token.token_type {
+                if let Some((_, mode)) = tokenizer.push_mode.iter().find(|(trigger, _)| trigger == operator) {
+                    state.modes.push(self.modes.iter().position(|(name, _)| name == mode).unwrap());
+                    changed = true;
+                } else if tokenizer.pop_mode.contains(operator) && !state.modes.is_empty() {
+                    state.modes.pop();
+                    changed = true;
+                }
+            }
+        }
+
+        if changed {
+            character_stack.adopt(&self.current(&state.modes).character_stack);
+        }
+        return success!(());
+    }
+
+    fn layout_tokens(&self, character_stack: &mut CharacterStack, token_stream: &mut Vec<Token>, notes: &mut Vec<Note>, state: &mut TokenizerState, complete: bool) -> Status<()> {
+        let layout_tokenizer = match &self.layout_tokenizer {
+            Some(layout_tokenizer) => layout_tokenizer,
+            None => return self.find_tokens(character_stack, token_stream, notes, &mut state.interpolations, complete),
+        };
+
+        character_stack.start_positions();
+        layout_tokenizer.measure(character_stack, token_stream, &mut state.layout, complete);
+        if character_stack.is_empty() {
+            return success!(());
+        }
+
+        let length = token_stream.len();
+        confirm!(self.find_tokens(character_stack, token_stream, notes, &mut state.interpolations, complete));
+        layout_tokenizer.insert(token_stream, length, &mut state.layout);
+        return success!(());
+    }
+
+    fn finish_tokens(&self, token_stream: &mut Vec<Token>, state: &mut TokenizerState, position: Position) {
+        if !state.interpolations.is_empty() {
+            let error = Error::UnterminatedToken(identifier!("string"));
+            token_stream.push(Token::new(TokenType::Invalid(error), vec![position.clone()]));
+            state.interpolations.clear();
+        }
+
+        if let Some(layout_tokenizer) = &self.current(&state.modes).layout_tokenizer {
+            layout_tokenizer.finish(token_stream, &mut state.layout, position);
+        }
+    }
+
+    // explanations name the partial that claimed each token and the partials that were checked before it
+    fn find_tokens(&self, character_stack: &mut CharacterStack, token_stream: &mut Vec<Token>, notes: &mut Vec<Note>, interpolations: &mut Vec<Interpolation>, complete: bool) -> Status<()> {
+        let length = token_stream.len();
+        let mut declined = Vec::new();
+        let claimed = confirm!(self.claim_tokens(character_stack, token_stream, notes, interpolations, complete, &mut declined));
+
+        if self.explain {
+            let reason = match declined.is_empty() {
+                true => String::from("as the first partial checked"),
+                false => format!("after {} declined", declined.join(", ")),
+            };
+
+            for token in token_stream.iter().skip(length) {
+                if let Some(position) = token.position.first() {
+                    let message = format_shared!("claimed by the {} tokenizer {}", claimed, reason);
+                    notes.push(Note::new(identifier!("explain"), message, position.clone()));
+                }
+            }
+        }
+        return success!(());
+    }
+
+    fn claim_tokens(&self, character_stack: &mut CharacterStack, token_stream: &mut Vec<Token>, notes: &mut Vec<Note>, interpolations: &mut Vec<Interpolation>, complete: bool, declined: &mut Vec<&'static str>) -> Status<&'static str> {
+        let mut error = None;
+        character_stack.start_positions();
+
+        if let Some(string_tokenizer) = &self.string_tokenizer {
+            if string_tokenizer.close(character_stack, token_stream, interpolations) {
+                return success!("string");
+            }
+        }
+
+        find!(declined, "comment", &self.comment_tokenizer, character_stack, token_stream, notes);
+        find!(declined, "pattern", &self.pattern_tokenizer, character_stack, token_stream);
+
+        for partial in self.partials.iter() {
+            if confirm!(partial.find(character_stack, token_stream)) {
+                return success!(partial.name());
+            }
+            declined.push(partial.name());
+        }
+
+        find!(declined, "number", &self.number_tokenizer, character_stack, token_stream, &mut error);
+        find!(declined, "character", &self.character_tokenizer, character_stack, token_stream);
+        find!(declined, "string", &self.string_tokenizer, character_stack, token_stream, interpolations);
+        find!(declined, "operator", &self.operator_tokenizer, character_stack, token_stream, complete);
+        find!(declined, "keyword", &self.keyword_tokenizer, character_stack, token_stream, complete);
+        find!(declined, "identifier", &self.identifier_tokenizer, character_stack, token_stream, complete, &mut error);
+
+        let word = confirm!(character_stack.till_breaking());
+        let positions = character_stack.final_positions();
+        let error = error.unwrap_or(Error::UnregisteredCharacter(character!(word[0])));
+        token_stream.push(Token::new(TokenType::Invalid(error), positions));
+        return success!("fallback");
+    }
+
     pub fn tokenize(&self, source_string: SharedString, source_file: Option<SharedString>, complete: bool) -> Status<(Vec<Token>, VariantRegistry, Vec<Note>)> {
+        if self.chunk_size.is_some() {
+            let mut token_iterator = self.stream(Cursor::new(source_string.unicode().into_bytes()), source_file, complete);
+            let mut token_stream = Vec::new();
+            let mut notes = Vec::new();
+            while let Some(token) = token_iterator.next() {
+                token_stream.push(confirm!(token));
+                notes.extend(token_iterator.take_notes());
+            }
+            return success!((token_stream, self.variant_registry.clone(), notes));
+        }
+
         let mut character_stack = self.character_stack.derive(source_string, source_file);
         let mut token_stream = Vec::new();
         let mut notes = Vec::new();
+        let mut state = TokenizerState::new();
 
         while !character_stack.is_empty() {
-            let mut error = None;
-            character_stack.start_positions();
+            confirm!(self.next_tokens(&mut character_stack, &mut token_stream, &mut notes, &mut state, complete));
+        }
+        self.finish_tokens(&mut token_stream, &mut state, character_stack.current_position());
 
-            find!(&self.comment_tokenizer, &mut character_stack, &mut token_stream, &mut notes);
-            find!(&self.number_tokenizer, &mut character_stack, &mut token_stream, &mut error);
-            find!(&self.character_tokenizer, &mut character_stack, &mut token_stream);
-            find!(&self.string_tokenizer, &mut character_stack, &mut token_stream);
-            find!(&self.operator_tokenizer, &mut character_stack, &mut token_stream, complete);
-            find!(&self.keyword_tokenizer, &mut character_stack, &mut token_stream, complete);
-            find!(&self.identifier_tokenizer, &mut character_stack, &mut token_stream, complete, &mut error);
+        return success!((token_stream, self.variant_registry.clone(), notes));
+    }
+
+    // the iterator keeps its own session, so positions of the streamed file stay valid while it is alive
+    pub fn stream<'tokenizer, R: Read + 'static>(&'tokenizer self, reader: R, source_file: Option<SharedString>, complete: bool) -> TokenIterator<'tokenizer> {
+        let session = SourceMap::session();
+        let chunk_size = self.chunk_size.unwrap_or(CHUNK_SIZE);
+        let character_stack = self.character_stack.derive_reader(Box::new(reader), source_file, chunk_size);
+        return TokenIterator::new(self, character_stack, session, complete);
+    }
 
-            let word = confirm!(character_stack.till_breaking());
-            let positions = character_stack.final_positions();
-            let error = error.unwrap_or(Error::UnregisteredCharacter(character!(word[0])));
-            token_stream.push(Token::new(TokenType::Invalid(error), positions));
+    pub fn variant_registry(&self) -> VariantRegistry {
+        return self.variant_registry.clone();
+    }
+}
+
+// yields tokens while reading, only the current token and its lookahead are kept in memory
+// notes have to be taken while streaming, only the most recent ones are kept otherwise
+pub struct TokenIterator<'tokenizer> {
+    tokenizer:          &'tokenizer Tokenizer,
+    character_stack:    CharacterStack,
+    _session:           SourceSession,
+    pending:            VecDeque<Token>,
+    notes:              Vec<Note>,
+    state:              TokenizerState,
+    complete:           bool,
+    failed:             bool,
+    finished:           bool,
+}
+
+impl<'tokenizer> TokenIterator<'tokenizer> {
+
+    fn new(tokenizer: &'tokenizer Tokenizer, character_stack: CharacterStack, session: SourceSession, complete: bool) -> Self {
+        Self {
+            tokenizer:          tokenizer,
+            character_stack:    character_stack,
+            _session:           session,
+            pending:            VecDeque::new(),
+            notes:              Vec::new(),
+            state:              TokenizerState::new(),
+            complete:           complete,
+            failed:             false,
+            finished:           false,
         }
+    }
 
-        return success!((token_stream, self.variant_registry.clone(), notes));
+    pub fn take_notes(&mut self) -> Vec<Note> {
+        return std::mem::replace(&mut self.notes, Vec::new());
+    }
+}
+
+impl<'tokenizer> Iterator for TokenIterator<'tokenizer> {
+    type Item = Status<Token>;
+
+    fn next(&mut self) -> Option<Status<Token>> {
+        while self.pending.is_empty() {
+            if self.failed || self.finished {
+                return None;
+            }
+
+            let mut token_stream = Vec::new();
+            let empty = self.character_stack.is_empty();
+            if let Some(error) = self.character_stack.take_read_error() {
+                self.failed = true;
+                return Some(Status::Error(error));
+            }
+
+            if empty {
+                let position = self.character_stack.current_position();
+                self.tokenizer.finish_tokens(&mut token_stream, &mut self.state, position);
+                self.pending.extend(token_stream.into_iter());
+                self.finished = true;
+                continue;
+            }
+
+            if let Status::Error(error) = self.tokenizer.next_tokens(&mut self.character_stack, &mut token_stream, &mut self.notes, &mut self.state, self.complete) {
+                self.failed = true;
+                return Some(Status::Error(error));
+            }
+
+            if let Some(error) = self.character_stack.take_read_error() {
+                self.failed = true;
+                return Some(Status::Error(error));
+            }
+
+            if self.notes.len() > STREAMED_NOTES {
+                let dropped = self.notes.len() - STREAMED_NOTES;
+                self.notes.drain(..dropped);
+            }
+            self.pending.extend(token_stream.into_iter());
+        }
+        return self.pending.pop_front().map(|token| Status::Success(token));
     }
 }
@@ -47,6 +47,10 @@ impl CharacterTokenizer {
         });
     }
 
+    pub fn delimiters(&self) -> Vec<SharedString> {
+        return self.delimiters.iter().map(|(start_delimiter, _)| start_delimiter.clone()).collect();
+    }
+
     pub fn find(&self, character_stack: &mut CharacterStack, tokens: &mut Vec<Token>) -> Status<bool> {
         for (start_delimiter, end_delimiter) in self.delimiters.iter() {
             if character_stack.check_string(&start_delimiter) {
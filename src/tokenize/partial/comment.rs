@@ -6,6 +6,8 @@ use tokenize::Token;
 pub struct CommentTokenizer {
     delimiters:     Vec<(SharedString, SharedString)>,
     notes:          Vec<(SharedString, Data)>,
+    documentation:  Vec<SharedString>,
+    nested:         bool,
 }
 
 impl CommentTokenizer {
@@ -40,6 +42,21 @@ impl CommentTokenizer {
             }
         }
 
+        let nested = match confirm!(settings.index(&keyword!("nested"))) {
+            Some(nested) => unpack_boolean!(&nested),
+            None => false,
+        };
+
+        let mut documentation = Vec::new();
+        if let Some(prefixes) = confirm!(settings.index(&keyword!("documentation"))) {
+            for prefix in unpack_list!(&prefixes).iter() {
+                let prefix = unpack_literal!(prefix);
+                let valid = delimiters.iter().any(|(start_delimiter, _): &(SharedString, SharedString)| prefix.len() > start_delimiter.len() && prefix.chars().take(start_delimiter.len()).eq(start_delimiter.chars()));
+                ensure!(valid, string!("documentation prefix {} does not extend a comment delimiter", prefix));
+                push_by_length!(documentation, prefix);
+            }
+        }
+
         if let Some(notes_lookup) = confirm!(settings.index(&keyword!("notes"))) {
             ensure!(notes_lookup.is_map(), ExpectedFound, expected_list!["map"], notes_lookup.clone());
 
@@ -54,16 +71,66 @@ impl CommentTokenizer {
         return success!(Self {
             delimiters:     delimiters,
             notes:          notes,
+            documentation:  documentation,
+            nested:         nested,
         });
     }
 
+    // repeating the last prefix character (////, /***) or closing the comment right away (/**/) is not documentation
+    fn check_documentation(&self, character_stack: &mut CharacterStack, start_delimiter: &SharedString, end_delimiter: &SharedString) -> bool {
+        for prefix in self.documentation.iter() {
+            if prefix.len() <= start_delimiter.len() || !prefix.chars().take(start_delimiter.len()).eq(start_delimiter.chars()) {
+                continue;
+            }
+
+            let remainder: SharedString = prefix.chars().skip(start_delimiter.len()).cloned().collect();
+            character_stack.save();
+            let closed = character_stack.check_string(end_delimiter);
+            character_stack.restore();
+
+            if closed {
+                continue;
+            }
+
+            character_stack.save();
+            if character_stack.check_string(&remainder) && character_stack.peek(0) != Some(remainder[remainder.len() - 1]) {
+                character_stack.drop();
+                return true;
+            }
+            character_stack.restore();
+        }
+        return false;
+    }
+
+    pub fn delimiters(&self) -> Vec<SharedString> {
+        return self.delimiters.iter().map(|(start_delimiter, _)| start_delimiter.clone()).collect();
+    }
+
     pub fn find(&self, character_stack: &mut CharacterStack, tokens: &mut Vec<Token>, notes: &mut Vec<Note>) -> Status<bool> {
         for (start_delimiter, end_delimiter) in self.delimiters.iter() {
             if character_stack.check_string(&start_delimiter) {
+                let documentation = self.check_documentation(character_stack, start_delimiter, end_delimiter);
+                let nested = self.nested && *end_delimiter != SharedString::from("\n");
                 let mut comment_string = SharedString::new();
+                let mut depth = 0;
                 let mut note = None;
 
-                'find: while !character_stack.check_string(&end_delimiter) {
+                'find: loop {
+                    if character_stack.check_string(&end_delimiter) {
+                        if depth == 0 {
+                            break;
+                        }
+                        comment_string.push_str(end_delimiter);
+                        depth -= 1;
+                        continue;
+                    }
+
+                    if nested && character_stack.check_string(&start_delimiter) {
+                        comment_string.push_str(start_delimiter);
+                        depth += 1;
+                        continue;
+                    }
+
                     if character_stack.is_empty() {
                         let error = Error::UnterminatedToken(identifier!("comment"));
                         tokens.push(Token::new(TokenType::Invalid(error), character_stack.final_positions()));
@@ -89,7 +156,7 @@ impl CommentTokenizer {
                     } else {
                         if let Some(note) = &mut note {
                             note.message.push(character);
-                            note.position.length += 1;
+                            note.position.end += 1;
                         }
                     }
                     comment_string.push(character);
@@ -99,7 +166,10 @@ impl CommentTokenizer {
                     notes.push(note);
                 }
 
-                tokens.push(Token::new(TokenType::Comment(comment_string), character_stack.final_positions()));
+                match documentation {
+                    true => tokens.push(Token::new(TokenType::Documentation(comment_string), character_stack.final_positions())),
+                    false => tokens.push(Token::new(TokenType::Comment(comment_string), character_stack.final_positions())),
+                }
                 return success!(true);
             }
         }
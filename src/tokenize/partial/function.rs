@@ -0,0 +1,127 @@
+use internal::*;
+use debug::*;
+
+use tokenize::Token;
+
+// characters passed to the function when the settings do not limit them
+static DEFAULT_LOOKAHEAD: usize = 256;
+
+pub trait TokenizerPartial {
+
+    // called when the partial is added to a tokenizer, once for the tokenizer and once for each of its modes
+    fn register(&mut self, _character_stack: &mut CharacterStack, _variant_registry: &mut VariantRegistry) -> Status<()> {
+        return success!(());
+    }
+
+    // used when explaining which partial claimed a token
+    fn name(&self) -> &'static str {
+        return "native";
+    }
+
+    // names of the custom tokens the partial may produce, used to find conflicts with patterns and other partials
+    fn custom(&self) -> Vec<SharedString> {
+        return Vec::new();
+    }
+
+    // called at the start of every token, returns true if a token was pushed
+    fn find(&self, character_stack: &mut CharacterStack, tokens: &mut Vec<Token>) -> Status<bool>;
+}
+
+pub struct FunctionTokenizer {
+    function:       Data,
+    lookahead:      usize,
+    custom:         Vec<SharedString>,
+    registry:       VariantRegistry,
+    root:           Data,
+    build:          Data,
+}
+
+impl FunctionTokenizer {
+
+    pub fn new(settings: &Data, compiler: &Data, variant_registry: &mut VariantRegistry) -> Status<Self> {
+        ensure!(settings.is_map(), ExpectedFound, expected_list!["map"], settings.clone());
+        let function = expect!(confirm!(settings.index(&keyword!("function"))), string!("function tokenizer is missing a function"));
+
+        let lookahead = match confirm!(settings.index(&keyword!("lookahead"))) {
+            Some(lookahead) => {
+                let lookahead = unpack_integer!(&lookahead);
+                ensure!(lookahead > 0, string!("lookahead must be bigger than zero"));
+                lookahead as usize
+            },
+            None => DEFAULT_LOOKAHEAD,
+        };
+
+        let mut custom = Vec::new();
+        if let Some(names) = confirm!(settings.index(&keyword!("custom"))) {
+            for name in unpack_list!(&names).iter() {
+                let name = unpack_identifier!(name);
+                variant_registry.register_pattern(name.clone());
+                custom.push(name);
+            }
+        }
+
+        return success!(Self {
+            function:       function,
+            lookahead:      lookahead,
+            custom:         custom,
+            registry:       variant_registry.clone(),
+            root:           compiler.clone(),
+            build:          map!(),
+        });
+    }
+
+    // the function returns [ kind value length ], a keyword kind is a token type and an identifier kind names a custom token
+    // custom tokens, operators and keywords have to be known to the tokenizer
+    fn create_token(&self, result: &Data, file: usize) -> Status<(TokenType, usize)> {
+        let items = unpack_list!(result);
+        ensure!(items.len() == 3, InvalidItemCount, integer!(3), integer!(items.len() as i64));
+
+        let length = unpack_integer!(&items[2]);
+        ensure!(length > 0, string!("function tokenizer must consume at least one character"));
+
+        let token_type = match items[0].is_identifier() {
+            true => TokenType::Custom(unpack_identifier!(&items[0]), unpack_string!(&items[1])),
+            false => confirm!(Token::deserialize(&list!(vector![items[0].clone(), items[1].clone(), list!()]), file)).token_type,
+        };
+
+        match &token_type {
+            TokenType::Custom(name, _) => ensure!(self.registry.is_pattern(name), string!("function tokenizer returned unregistered custom token {}", name)),
+            TokenType::Operator(operator) => ensure!(self.registry.is_operator(operator), string!("function tokenizer returned unregistered operator {}", operator)),
+            TokenType::Keyword(keyword) => ensure!(self.registry.is_keyword(keyword), string!("function tokenizer returned unregistered keyword {}", keyword)),
+            _other => { },
+        }
+        return success!((token_type, length as usize));
+    }
+}
+
+impl TokenizerPartial for FunctionTokenizer {
+
+    fn name(&self) -> &'static str {
+        return "function";
+    }
+
+    fn custom(&self) -> Vec<SharedString> {
+        return self.custom.clone();
+    }
+
+    fn find(&self, character_stack: &mut CharacterStack, tokens: &mut Vec<Token>) -> Status<bool> {
+        let position = character_stack.current_position();
+        let remaining = character_stack.remaining(self.lookahead);
+        if remaining.is_empty() {
+            return success!(false);
+        }
+
+        let parameters = vector![Data::String(remaining.clone()), position.serialize()];
+        let result = match confirm!(function(&self.function, parameters, &None, &self.root, &self.build), Tag, self.function.clone()) {
+            Some(result) if result != identifier!("none") => result,
+            _other => return success!(false),
+        };
+
+        let (token_type, length) = confirm!(self.create_token(&result, position.file), Tag, self.function.clone());
+        ensure!(length <= remaining.len(), string!("function tokenizer consumed {} characters but only {} are left", length, remaining.len()));
+        character_stack.advance(length);
+
+        tokens.push(Token::new(token_type, character_stack.final_positions()));
+        return success!(true);
+    }
+}
@@ -35,6 +35,28 @@ impl IdentifierTokenizer {
             }
         }
 
+        let follow = match confirm!(settings.index(&keyword!("continue"))) {
+            Some(follow) => {
+                let follow = confirm!(CharacterClass::deserialize_list(&follow));
+                confirm!(character_stack.register_classes(&follow));
+                Some(follow)
+            }
+            None => None,
+        };
+
+        // type classes are added first so they take precedence over overlapping identifier classes
+        if let Some(type_start) = confirm!(settings.index(&keyword!("type_start"))) {
+            let type_start = confirm!(CharacterClass::deserialize_list(&type_start));
+            confirm!(character_stack.register_classes(&type_start));
+            rules.add_class(type_start, follow.clone(), Action::Map(SharedString::from("type_identifier")));
+        }
+
+        if let Some(start) = confirm!(settings.index(&keyword!("start"))) {
+            let start = confirm!(CharacterClass::deserialize_list(&start));
+            confirm!(character_stack.register_classes(&start));
+            rules.add_class(start, follow.clone(), Action::Map(SharedString::from("identifier")));
+        }
+
         if let Some(invalid_identifiers) = confirm!(settings.index(&keyword!("invalid"))) {
             for identifier in unpack_list!(&invalid_identifiers).into_iter() {
                 let identifier = unpack_identifier!(&identifier);
@@ -59,15 +81,37 @@ impl IdentifierTokenizer {
         });
     }
 
+    pub fn prefixes(&self) -> Vec<SharedString> {
+        return self.rules.patterns();
+    }
+
+    // returns the literal identifier prefix the word starts with, classes are not considered
+    pub fn prefix(&self, word: &SharedString) -> Option<SharedString> {
+        match self.rules.check_pattern_prefix(word) {
+            Some((prefix, action)) if action.is_mapped_to("identifier") || action.is_mapped_to("type_identifier") => return Some(prefix),
+            _other => return None,
+        }
+    }
+
     pub fn find(&self, character_stack: &mut CharacterStack, tokens: &mut Vec<Token>, complete: bool, error: &mut Option<Error>) -> Status<bool> {
         character_stack.save();
-        let word = confirm!(character_stack.till_breaking());
+        let mut word = confirm!(character_stack.till_breaking());
 
         if !character_stack.is_pure(&word) {
             character_stack.restore();
             return success!(false);
         }
 
+        // characters outside of the continue classes end the identifier early
+        if let Some((matched, _action)) = self.rules.check_class_prefix(&word) {
+            if matched.len() < word.len() {
+                character_stack.restore();
+                character_stack.save();
+                character_stack.advance(matched.len());
+                word = matched;
+            }
+        }
+
         let mut buffer = word.clone();
         loop {
             if let Some((matched, action)) = self.rules.check_prefix(&buffer) {
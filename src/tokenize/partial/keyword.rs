@@ -5,6 +5,7 @@ use tokenize::Token;
 
 pub struct KeywordTokenizer {
     rules:      Rules,
+    contextual: Vec<SharedString>,
 }
 
 impl KeywordTokenizer {
@@ -12,6 +13,7 @@ impl KeywordTokenizer {
     pub fn new(settings: &Data, character_stack: &mut CharacterStack, variant_registry: &mut VariantRegistry) -> Status<Self> {
         ensure!(settings.is_map(), ExpectedFound, expected_list!["map"], settings.clone());
         let mut rules = Rules::new();
+        let mut contextual = Vec::new();
 
         if let Some(translate_lookup) = confirm!(settings.index(&keyword!("translate"))) {
             ensure!(translate_lookup.is_map(), ExpectedFound, expected_list!["map"], translate_lookup);
@@ -25,6 +27,20 @@ impl KeywordTokenizer {
             }
         }
 
+        // contextual keywords stay identifiers unless the grammar expects the keyword
+        if let Some(contextual_lookup) = confirm!(settings.index(&keyword!("contextual"))) {
+            ensure!(contextual_lookup.is_map(), ExpectedFound, expected_list!["map"], contextual_lookup);
+
+            for (from, to) in confirm!(contextual_lookup.pairs()).into_iter() {
+                let from = unpack_identifier!(&from);
+                let to = unpack_identifier!(&to);
+                variant_registry.register_keyword(to.clone());
+                confirm!(character_stack.register_pure(&from));
+                confirm!(rules.add(from.clone(), Action::Map(to)));
+                contextual.push(from);
+            }
+        }
+
         if let Some(invalid_list) = confirm!(settings.index(&keyword!("invalid"))) {
             for keyword in unpack_list!(&invalid_list).into_iter() {
                 let keyword = unpack_identifier!(&keyword);
@@ -43,9 +59,14 @@ impl KeywordTokenizer {
 
         return success!(Self {
             rules:          rules,
+            contextual:     contextual,
         });
     }
 
+    pub fn words(&self) -> Vec<SharedString> {
+        return self.rules.patterns();
+    }
+
     pub fn find(&self, character_stack: &mut CharacterStack, tokens: &mut Vec<Token>, complete: bool) -> Status<bool> {
         character_stack.save();
         let word = confirm!(character_stack.till_breaking());
@@ -60,6 +81,11 @@ impl KeywordTokenizer {
 
             match action {
 
+                Action::Map(keyword) if self.contextual.contains(&matched) => {
+                    tokens.push(Token::new(TokenType::Contextual(matched, keyword), character_stack.final_positions()));
+                    return success!(true);
+                },
+
                 Action::Map(keyword) => {
                     tokens.push(Token::new(TokenType::Keyword(keyword), character_stack.final_positions()));
                     return success!(true);
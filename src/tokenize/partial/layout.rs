@@ -0,0 +1,153 @@
+use internal::*;
+use debug::*;
+
+use tokenize::Token;
+
+pub struct Layout {
+    levels:         Vec<SharedString>,
+    pending:        Option<(SharedString, Vec<Position>)>,
+    newline:        Option<Vec<Position>>,
+    line_tokens:    bool,
+}
+
+impl Layout {
+
+    pub fn new() -> Self {
+        Self {
+            levels:         vec![SharedString::new()],
+            pending:        None,
+            newline:        None,
+            line_tokens:    false,
+        }
+    }
+}
+
+pub struct LayoutTokenizer {
+    indentation:    Vec<Character>,
+}
+
+impl LayoutTokenizer {
+
+    pub fn new(settings: &Data, character_stack: &mut CharacterStack, variant_registry: &mut VariantRegistry) -> Status<Self> {
+        ensure!(settings.is_map(), ExpectedFound, expected_list!["map"], settings.clone());
+        let mut indentation = vec![Character::from_char(' '), Character::from_char('\t')];
+
+        if let Some(characters) = confirm!(settings.index(&keyword!("indentation"))) {
+            indentation = Vec::new();
+            for character in unpack_list!(&characters).into_iter() {
+                let character = unpack_character!(&character);
+                ensure!(!character.is_newline(), string!("newlines may not be used as indentation"));
+                confirm!(character_stack.register_breaking(character));
+                indentation.push(character);
+            }
+        }
+
+        variant_registry.has_layout = true;
+
+        return success!(Self {
+            indentation:    indentation,
+        });
+    }
+
+    // the indentation is only applied once a token is found on the line, so blank lines and comments do not change the layout
+    pub fn measure(&self, character_stack: &mut CharacterStack, tokens: &mut Vec<Token>, layout: &mut Layout, complete: bool) {
+        if !character_stack.is_line_start() {
+            return;
+        }
+
+        // newline tokens are placed on the line break that ends the last line with tokens
+        let position = character_stack.current_position();
+        if layout.line_tokens && layout.newline.is_none() && position.start > 0 {
+            layout.newline = Some(vec![Position::new(position.file, position.start - 1, position.start)]);
+        }
+
+        let mut indentation = SharedString::new();
+        while let Some(character) = character_stack.peek(0) {
+            if !self.indentation.contains(&character) {
+                break;
+            }
+            indentation.push(character);
+            character_stack.advance(1);
+        }
+
+        let positions = character_stack.final_positions();
+        if complete && !indentation.is_empty() {
+            tokens.push(Token::new(TokenType::Ignored, positions.clone()));
+        }
+
+        character_stack.start_positions();
+        layout.pending = Some((indentation, positions));
+    }
+
+    fn align(&self, indentation: SharedString, positions: &Vec<Position>, layout: &mut Layout) -> Vec<Token> {
+        let mut tokens = Vec::new();
+        let current = layout.levels.last().unwrap().clone();
+
+        if indentation == current {
+            return tokens;
+        }
+
+        if indentation.len() > current.len() && indentation.chars().take(current.len()).eq(current.chars()) {
+            layout.levels.push(indentation);
+            tokens.push(Token::new(TokenType::Indent, positions.clone()));
+            return tokens;
+        }
+
+        while layout.levels.len() > 1 && layout.levels.last().unwrap().len() > indentation.len() {
+            layout.levels.pop();
+            tokens.push(Token::new(TokenType::Dedent, positions.clone()));
+        }
+
+        // the indentation becomes the new level so following lines are not reported again
+        if *layout.levels.last().unwrap() != indentation {
+            let error = Error::InconsistentIndentation(string!(String, indentation.clone()));
+            tokens.push(Token::new(TokenType::Invalid(error), positions.clone()));
+            layout.levels.push(indentation);
+        }
+        return tokens;
+    }
+
+    pub fn insert(&self, tokens: &mut Vec<Token>, start: usize, layout: &mut Layout) {
+        let index = match tokens.iter().skip(start).position(|token| token.is_content()) {
+            Some(offset) => start + offset,
+            None => return,
+        };
+
+        let (indentation, mut positions) = match layout.pending.take() {
+            Some(pending) => pending,
+            None => {
+                layout.line_tokens = true;
+                return;
+            }
+        };
+
+        if positions.is_empty() {
+            positions = tokens[index].position.clone();
+        }
+
+        let mut layout_tokens = Vec::new();
+        if layout.line_tokens {
+            let newline = layout.newline.take().unwrap_or(positions.clone());
+            layout_tokens.push(Token::new(TokenType::Newline, newline));
+        }
+        layout_tokens.extend(self.align(indentation, &positions, layout));
+        layout.line_tokens = true;
+
+        let remaining = tokens.split_off(index);
+        tokens.extend(layout_tokens.into_iter());
+        tokens.extend(remaining.into_iter());
+    }
+
+    // tokens closing the file point at its end
+    pub fn finish(&self, tokens: &mut Vec<Token>, layout: &mut Layout, position: Position) {
+        if layout.line_tokens {
+            tokens.push(Token::new(TokenType::Newline, layout.newline.take().unwrap_or(vec![position.clone()])));
+            layout.line_tokens = false;
+        }
+
+        while layout.levels.len() > 1 {
+            layout.levels.pop();
+            tokens.push(Token::new(TokenType::Dedent, vec![position.clone()]));
+        }
+    }
+}
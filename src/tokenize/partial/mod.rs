@@ -5,6 +5,9 @@ mod string;
 mod keyword;
 mod operator;
 mod identifier;
+mod layout;
+mod pattern;
+mod function;
 
 pub use self::comment::*;
 pub use self::number::*;
@@ -13,3 +16,6 @@ pub use self::string::*;
 pub use self::keyword::*;
 pub use self::operator::*;
 pub use self::identifier::*;
+pub use self::layout::*;
+pub use self::pattern::*;
+pub use self::function::*;
@@ -10,6 +10,74 @@ fn to_length(option: &Option<SharedString>) -> usize {
     }
 }
 
+fn strip_suffix(source: &SharedString, suffix: &SharedString) -> Option<SharedString> {
+    if suffix.len() >= source.len() || !source.chars().skip(source.len() - suffix.len()).eq(suffix.chars()) {
+        return None;
+    }
+    return Some(source.chars().take(source.len() - suffix.len()).cloned().collect());
+}
+
+// the exponent holds the marker, whether it is negative and its digits
+#[derive(Clone)]
+struct Literal {
+    integer:            SharedString,
+    fraction:           Option<SharedString>,
+    exponent:           Option<(SharedString, bool, SharedString)>,
+}
+
+impl Literal {
+
+    pub fn new(integer: SharedString, fraction: Option<SharedString>) -> Self {
+        Self {
+            integer:            integer,
+            fraction:           fraction,
+            exponent:           None,
+        }
+    }
+
+    fn mantissa(&mut self) -> &mut SharedString {
+        if let Some(ref mut fraction) = self.fraction {
+            return fraction;
+        }
+        return &mut self.integer;
+    }
+
+    // suffixes are always part of the last word of the literal
+    fn last(&mut self) -> &mut SharedString {
+        if let Some((_, _, ref mut digits)) = self.exponent {
+            return digits;
+        }
+        return self.mantissa();
+    }
+
+    pub fn strip_last(&self, suffix: &SharedString) -> Option<Self> {
+        let mut literal = self.clone();
+        let stripped = strip_suffix(literal.last(), suffix)?;
+        *literal.last() = stripped;
+        return Some(literal);
+    }
+}
+
+// a digit may be written with several characters, like 'f' and 'F' in hexadecimal
+#[derive(Clone)]
+struct NumberSystem {
+    characters:         Vec<Character>,
+    values:             Vec<u64>,
+    base:               u64,
+}
+
+impl NumberSystem {
+
+    pub fn contains(&self, character: &Character) -> bool {
+        return self.characters.contains(character);
+    }
+
+    pub fn value(&self, character: &Character) -> Option<u64> {
+        let index = self.characters.iter().position(|digit| digit == character)?;
+        return Some(self.values[index]);
+    }
+}
+
 struct Format {
     pub variants:       Vec<(Option<SharedString>, SharedString)>,
     suffixes:           Vec<SharedString>,
@@ -38,12 +106,12 @@ impl Format {
         return success!(());
     }
 
-    pub fn add(&mut self, suffix: Option<SharedString>, number_system: SharedString, number_systems: &Map<SharedString, Vec<Character>>) -> Status<()> {
+    pub fn add(&mut self, suffix: Option<SharedString>, number_system: SharedString, number_systems: &Map<SharedString, NumberSystem>) -> Status<()> {
         if let Some(suffix) = &suffix {
             self.suffixes.push(suffix.clone());
         } else {
             let number_system = number_systems.get(&number_system).unwrap();
-            for digit in number_system.iter() {
+            for digit in number_system.characters.iter() {
                 if !self.digits.contains(digit) {
                     self.digits.push(*digit);
                 }
@@ -60,10 +128,13 @@ impl Format {
 }
 
 pub struct NumberTokenizer {
-    number_systems:     Map<SharedString, Vec<Character>>,
+    number_systems:     Map<SharedString, NumberSystem>,
     formats:            Vec<(Option<SharedString>, Format)>,
     float_delimiters:   Vec<SharedString>,
     negatives:          Vec<SharedString>,
+    exponents:          Vec<SharedString>,
+    separators:         Vec<Character>,
+    types:              Vec<SharedString>,
 }
 
 impl NumberTokenizer {
@@ -80,19 +151,34 @@ impl NumberTokenizer {
 
         for (name, digits) in confirm!(index_field!(settings, "systems").pairs()).into_iter() {
             let name = unpack_identifier!(&name);
-            let mut collected_digits = Vec::new();
+            let digits = unpack_list!(&digits);
+            let mut characters = Vec::new();
+            let mut values = Vec::new();
+
+            for (value, digit) in digits.iter().enumerate() {
+                let variants = match digit.is_list() {
+                    true => unpack_list!(digit),
+                    false => vector![digit.clone()],
+                };
 
-            for character in unpack_list!(&digits).into_iter() {
-                let character = unpack_character!(&character);
-                confirm!(character_stack.register_non_breaking(character));
-                collected_digits.push(character);
-                if !all_digits.contains(&character) {
-                    all_digits.push(character);
+                for character in variants.iter() {
+                    let character = unpack_character!(character);
+                    ensure!(!characters.contains(&character), string!("digit {} is used twice", character.serialize()));
+                    confirm!(character_stack.register_non_breaking(character));
+                    characters.push(character);
+                    values.push(value as u64);
+                    if !all_digits.contains(&character) {
+                        all_digits.push(character);
+                    }
                 }
             }
 
-            ensure!(collected_digits.len() >= 2, string!("number system needs at least two digits"));
-            number_systems.insert(name, collected_digits);
+            ensure!(digits.len() >= 2, string!("number system needs at least two digits"));
+            number_systems.insert(name, NumberSystem {
+                characters:         characters,
+                values:             values,
+                base:               digits.len() as u64,
+            });
         }
 
         if let Some(format_lookup) = confirm!(settings.index(&keyword!("formats"))) {
@@ -165,7 +251,7 @@ impl NumberTokenizer {
 
         if let Some((_, format)) = formats.iter().find(|(prefix, _)| prefix.is_none()) {
             if let Some((_suffix, system)) = format.variants.iter().find(|(suffix, _)| suffix.is_none()) {
-                for character in number_systems.get(system).unwrap().iter() {
+                for character in number_systems.get(system).unwrap().characters.iter() {
                     confirm!(character_stack.register_signature(character.to_string()));
                 }
             }
@@ -192,93 +278,271 @@ impl NumberTokenizer {
             }
         }
 
+        let mut exponents = Vec::new();
+        if let Some(markers) = confirm!(settings.index(&keyword!("exponents"))) {
+            for marker in unpack_list!(&markers).iter() {
+                let marker = unpack_literal!(marker);
+                ensure!(!marker.is_empty(), EmptyLiteral);
+                for character in marker.chars() {
+                    confirm!(character_stack.register_non_breaking(*character));
+                }
+                push_by_length!(exponents, marker);
+                variant_registry.has_floats = true;
+            }
+        }
+
+        let mut separators = Vec::new();
+        if let Some(characters) = confirm!(settings.index(&keyword!("separators"))) {
+            for character in unpack_list!(&characters).iter() {
+                let character = unpack_character!(character);
+                ensure!(!all_digits.contains(&character), string!("digit {} may not be used as a separator", character.serialize()));
+                confirm!(character_stack.register_non_breaking(character));
+                separators.push(character);
+            }
+        }
+
+        let mut types = Vec::new();
+        if let Some(type_list) = confirm!(settings.index(&keyword!("types"))) {
+            for type_suffix in unpack_list!(&type_list).iter() {
+                let type_suffix = unpack_literal!(type_suffix);
+                ensure!(!type_suffix.is_empty(), EmptyLiteral);
+                for character in type_suffix.chars() {
+                    confirm!(character_stack.register_non_breaking(*character));
+                }
+                push_by_length!(types, type_suffix);
+            }
+        }
+
         return success!(Self {
             number_systems:     number_systems,
             formats:            formats,
             float_delimiters:   float_delimiters,
             negatives:          negatives,
+            exponents:          exponents,
+            separators:         separators,
+            types:              types,
         });
     }
 
-    fn parse_number(&self, source: &SharedString, float_source: Option<&SharedString>, number_system: &SharedString, negative: bool, positions: &Vec<Position>) -> Option<Token> {
-        let number_system = self.number_systems.get(number_system).unwrap();
-        let base = number_system.len();
-        let mut value = 0;
-
-        'parse: for (index, character) in source.reverse_chars().enumerate() {
-            for (digit_index, digit) in number_system.iter().enumerate() {
-                if *character == *digit {
-                    value += digit_index * base.pow(index as u32);
-                    continue 'parse;
+    // separators may be placed between digits but not in front of them
+    fn digit_values(&self, source: &SharedString, number_system: &NumberSystem) -> Option<Vec<u64>> {
+        let mut values = Vec::new();
+        for character in source.chars() {
+            if self.separators.contains(character) {
+                if values.is_empty() {
+                    return None;
                 }
+                continue;
             }
-            return None;
+            values.push(number_system.value(character)?);
         }
 
-        if let Some(float_source) = float_source {
-            let mut float_value = 0;
+        match values.is_empty() {
+            true => return None,
+            false => return Some(values),
+        }
+    }
+
+    fn split_exponent(&self, mut literal: Literal, number_system: &NumberSystem) -> Option<Literal> {
+        if let Some((marker, _, _)) = literal.exponent.clone() {
+            if number_system.contains(&marker[0]) {
+                return None;
+            }
+            let stripped = strip_suffix(literal.mantissa(), &marker)?;
+            *literal.mantissa() = stripped;
+            return Some(literal);
+        }
+
+        // markers that are digits of the number system are digits, so 0x1e5 stays hexadecimal
+        for marker in self.exponents.iter() {
+            if number_system.contains(&marker[0]) {
+                continue;
+            }
 
-            'parse_float: for (index, character) in float_source.reverse_chars().enumerate() {
-                for (digit_index, digit) in number_system.iter().enumerate() {
-                    if *character == *digit {
-                        float_value += digit_index * base.pow(index as u32);
-                        continue 'parse_float;
+            let mantissa = literal.mantissa().clone();
+            if let Some(index) = mantissa.find(marker) {
+                if index > 0 && index + marker.len() < mantissa.len() {
+                    *literal.mantissa() = mantissa.chars().take(index).cloned().collect();
+                    literal.exponent = Some((marker.clone(), false, mantissa.chars().skip(index + marker.len()).cloned().collect()));
+                    return Some(literal);
+                }
+            }
+        }
+        return Some(literal);
+    }
+
+    fn parse_literal(&self, literal: Literal, number_system: &NumberSystem, negative: bool, positions: &Vec<Position>) -> Option<Token> {
+        let literal = self.split_exponent(literal, number_system)?;
+        let base = number_system.base;
+        let integer = self.digit_values(&literal.integer, number_system)?;
+
+        let fraction = match &literal.fraction {
+            Some(fraction) => Some(self.digit_values(fraction, number_system)?),
+            None => None,
+        };
+
+        let exponent = match &literal.exponent {
+            Some((_, negative_exponent, digits)) => {
+                let value = self.digit_values(digits, number_system)?.iter().fold(0i32, |value, digit| value.saturating_mul(base as i32).saturating_add(*digit as i32));
+                Some(if *negative_exponent { -value } else { value })
+            },
+            None => None,
+        };
+
+        if fraction.is_none() && exponent.is_none() {
+            let limit = match negative {
+                true => i64::max_value() as u64 + 1,
+                false => i64::max_value() as u64,
+            };
+
+            let mut value: u64 = 0;
+            for digit in integer.iter() {
+                match value.checked_mul(base).and_then(|value| value.checked_add(*digit)) {
+                    Some(next) if next <= limit => value = next,
+                    _overflow => {
+                        let error = Error::IntegerOverflow(string!(String, literal.integer.clone()));
+                        return Some(Token::new(TokenType::Invalid(error), positions.clone()));
                     }
                 }
-                return None;
             }
 
-            let temp = float_value as f64 / base.pow(float_source.len() as u32) as f64;
             match negative {
-                true => return Some(Token::new(TokenType::Float(-(value as f64 + temp)), positions.clone())), // dirty please fix
-                false => return Some(Token::new(TokenType::Float(value as f64 + temp), positions.clone())), // dirty please fix
+                true => return Some(Token::new(TokenType::Integer((value as i64).wrapping_neg()), positions.clone())),
+                false => return Some(Token::new(TokenType::Integer(value as i64), positions.clone())),
             }
         }
 
+        // decimal literals are rounded correctly by the standard parser, other systems scale all digits by a power of their base once
+        let fraction = fraction.unwrap_or(Vec::new());
+        let value = match base == 10 {
+
+            true => {
+                let digits = |values: &Vec<u64>| values.iter().map(|digit| std::char::from_digit(*digit as u32, 10).unwrap_or('0')).collect::<String>();
+                let decimal = format!("{}.{}e{}", digits(&integer), digits(&fraction), exponent.unwrap_or(0));
+                decimal.parse::<f64>().ok()?
+            }
+
+            false => {
+                let value = integer.iter().chain(fraction.iter()).fold(0.0, |value, digit| value * base as f64 + *digit as f64);
+                let scale = exponent.unwrap_or(0).saturating_sub(fraction.len() as i32);
+                match scale < 0 {
+                    true => value / (base as f64).powi(-scale),
+                    false => value * (base as f64).powi(scale),
+                }
+            }
+        };
+
+        if value.is_infinite() {
+            let mut source = literal.integer.clone();
+            if let Some(fraction) = &literal.fraction {
+                source.push(Character::from_char('.'));
+                source.push_str(fraction);
+            }
+            if let Some((marker, negative_exponent, digits)) = &literal.exponent {
+                source.push_str(marker);
+                if *negative_exponent {
+                    source.push(Character::from_char('-'));
+                }
+                source.push_str(digits);
+            }
+
+            let error = Error::FloatOverflow(string!(String, source));
+            return Some(Token::new(TokenType::Invalid(error), positions.clone()));
+        }
+
         match negative {
-            true => return Some(Token::new(TokenType::Integer(-(value as i64)), positions.clone())), // dirty please fix
-            false => return Some(Token::new(TokenType::Integer(value as i64), positions.clone())), // dirty please fix
+            true => return Some(Token::new(TokenType::Float(-value), positions.clone())),
+            false => return Some(Token::new(TokenType::Float(value), positions.clone())),
         }
     }
 
-    fn try_parse(&self, source: &SharedString, float_source: Option<&SharedString>, format: &Format, negative: bool, positions: &Vec<Position>) -> Option<Token> {
-        for (suffix, number_system) in format.variants.iter() {
-            if let Some(suffix) = suffix {
-                if let Some(float_source) = float_source {
-                    if suffix.len() < float_source.len() {
-                        let start = float_source.len() - suffix.len();
-                        let sliced = float_source.slice_end(start);
-                        if sliced == *suffix {
-                            if let Some(token) = self.parse_number(&source, Some(&sliced), number_system, negative, positions) {
-                                return Some(token);
-                            }
-                        }
-                    }
-                } else {
-                    if suffix.len() < source.len() {
-                        let start = source.len() - suffix.len();
-                        let sliced = source.slice_end(start);
-                        if sliced == *suffix {
-                            if let Some(token) = self.parse_number(&sliced, None, number_system, negative, positions) {
-                                return Some(token);
-                            }
-                        }
-                    }
+    // the literal is tried as a whole before removing a type, so 0x1f32 stays a hexadecimal number
+    fn try_parse(&self, literal: Literal, format: &Format, negative: bool, positions: &Vec<Position>) -> Option<Token> {
+        let mut candidates = vec![(literal.clone(), None)];
+        for type_suffix in self.types.iter() {
+            if let Some(stripped) = literal.strip_last(type_suffix) {
+                candidates.push((stripped, Some(type_suffix.clone())));
+            }
+        }
+
+        for (candidate, type_suffix) in candidates.into_iter() {
+            for (suffix, number_system) in format.variants.iter() {
+                let candidate = match suffix {
+                    Some(suffix) => match candidate.strip_last(suffix) {
+                        Some(stripped) => stripped,
+                        None => continue,
+                    },
+                    None => candidate.clone(),
+                };
+
+                let number_system = self.number_systems.get(number_system).unwrap();
+                if let Some(token) = self.parse_literal(candidate, number_system, negative, positions) {
+                    return Some(token.with_suffix(type_suffix));
                 }
-            } else {
-                if let Some(float_source) = float_source {
-                    if let Some(token) = self.parse_number(&source, Some(&float_source), number_system, negative, positions) {
-                        return Some(token);
-                    }
-                } else {
-                    if let Some(token) = self.parse_number(&source, None, number_system, negative, positions) {
-                        return Some(token);
+            }
+        }
+
+        return None;
+    }
+
+    // a sign breaks the word, so the exponent of 1.5e-3 is read after the marker
+    fn parse_exponent(&self, character_stack: &mut CharacterStack, tokens: &mut Vec<Token>, mut literal: Literal, format: &Format, negative: bool) -> bool {
+        let mantissa = literal.mantissa().clone();
+
+        for marker in self.exponents.iter() {
+            if strip_suffix(&mantissa, marker).is_none() {
+                continue;
+            }
+
+            let negative_exponent = match character_stack.peek(0).map(|character| character.as_char()) {
+                Some('-') => true,
+                Some('+') => false,
+                _other => continue,
+            };
+
+            character_stack.save();
+            character_stack.advance(1);
+            if let Status::Success(digits) = character_stack.till_breaking() {
+                let mut signed = literal.clone();
+                signed.exponent = Some((marker.clone(), negative_exponent, digits));
+                if let Some(token) = self.try_parse(signed, format, negative, &character_stack.final_positions()) {
+                    character_stack.drop();
+                    tokens.push(token);
+                    return true;
+                }
+            }
+            character_stack.restore();
+        }
+
+        if let Some(token) = self.try_parse(literal, format, negative, &character_stack.final_positions()) {
+            tokens.push(token);
+            return true;
+        }
+        return false;
+    }
+
+    fn parse_rest(&self, character_stack: &mut CharacterStack, tokens: &mut Vec<Token>, source: SharedString, format: &Format, negative: bool) -> bool {
+        for delimiter in &self.float_delimiters {
+            character_stack.save();
+            if character_stack.check_string(delimiter) {
+                if let Status::Success(float_source) = character_stack.till_breaking() {
+                    if self.parse_exponent(character_stack, tokens, Literal::new(source.clone(), Some(float_source)), format, negative) {
+                        character_stack.drop();
+                        return true;
                     }
                 }
             }
+            character_stack.restore();
         }
 
-        return None;
+        return self.parse_exponent(character_stack, tokens, Literal::new(source, None), format, negative);
+    }
+
+    // everything that may start a number other than its digits
+    pub fn signatures(&self) -> Vec<SharedString> {
+        let mut signatures: Vec<SharedString> = self.formats.iter().filter_map(|(prefix, _)| prefix.clone()).collect();
+        signatures.extend(self.negatives.iter().cloned());
+        return signatures;
     }
 
     pub fn find(&self, character_stack: &mut CharacterStack, tokens: &mut Vec<Token>, _error: &mut Option<Error>) -> Status<bool> {
@@ -297,29 +561,12 @@ impl NumberTokenizer {
                         }
 
                         let source = confirm!(character_stack.till_breaking());
-
-                        for delimiter in &self.float_delimiters {
-                            character_stack.save();
-                            if character_stack.check_string(delimiter) {
-                                if let Status::Success(float_source) = character_stack.till_breaking() {
-                                    if let Some(token) = self.try_parse(&source, Some(&float_source), format, negative, &character_stack.final_positions()) {
-                                        character_stack.drop();
-                                        tokens.push(token);
-                                        return success!(true);
-                                    }
-                                }
-                            }
-                            character_stack.restore();
-                        }
-
-                        if let Some(token) = self.try_parse(&source, None, format, negative, &character_stack.final_positions()) {
-                            tokens.push(token);
+                        if self.parse_rest(character_stack, tokens, source, format, negative) {
                             return success!(true);
                         }
 
                         let error = Error::ExpectedImmediate;
                         tokens.push(Token::new(TokenType::Invalid(error), character_stack.final_positions()));
-                        character_stack.drop();
                         return success!(true);
                     }
                 },
@@ -343,24 +590,8 @@ impl NumberTokenizer {
                         },
                     };
 
-                    for delimiter in &self.float_delimiters {
-                        character_stack.save();
-                        if character_stack.check_string(delimiter) {
-                            if let Status::Success(float_source) = character_stack.till_breaking() {
-                                if let Some(token) = self.try_parse(&source, Some(&float_source), format, negative, &character_stack.final_positions()) {
-                                    character_stack.drop();
-                                    character_stack.drop();
-                                    tokens.push(token);
-                                    return success!(true);
-                                }
-                            }
-                        }
-                        character_stack.restore();
-                    }
-
-                    if let Some(token) = self.try_parse(&source, None, format, negative, &character_stack.final_positions()) {
+                    if self.parse_rest(character_stack, tokens, source, format, negative) {
                         character_stack.drop();
-                        tokens.push(token);
                         return success!(true);
                     }
 
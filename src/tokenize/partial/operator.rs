@@ -5,6 +5,7 @@ use tokenize::Token;
 
 pub struct OperatorTokenizer {
     rules:      Rules,
+    classes:    Vec<CharacterClass>,
 }
 
 impl OperatorTokenizer {
@@ -12,6 +13,7 @@ impl OperatorTokenizer {
     pub fn new(settings: &Data, character_stack: &mut CharacterStack, variant_registry: &mut VariantRegistry) -> Status<Self> {
         ensure!(settings.is_map(), ExpectedFound, expected_list!["map"], settings.clone());
         let mut rules = Rules::new();
+        let mut classes = Vec::new();
 
         if let Some(translate_lookup) = confirm!(settings.index(&keyword!("translate"))) {
             ensure!(translate_lookup.is_map(), ExpectedFound, expected_list!["map"], translate_lookup.clone());
@@ -47,11 +49,37 @@ impl OperatorTokenizer {
             }
         }
 
+        if let Some(invalid_classes) = confirm!(settings.index(&keyword!("invalid_classes"))) {
+            for class in confirm!(CharacterClass::deserialize_list(&invalid_classes)).into_iter() {
+                character_stack.register_breaking_class(class.clone());
+                classes.push(class.clone());
+                rules.add_class(vec![class], None, Action::Invalid);
+            }
+        }
+
+        if let Some(ignored_classes) = confirm!(settings.index(&keyword!("ignored_classes"))) {
+            for class in confirm!(CharacterClass::deserialize_list(&ignored_classes)).into_iter() {
+                character_stack.register_breaking_class(class.clone());
+                classes.push(class.clone());
+                rules.add_class(vec![class], None, Action::Ignored);
+            }
+        }
+
         return success!(Self {
             rules:          rules,
+            classes:        classes,
         });
     }
 
+    pub fn operators(&self) -> Vec<SharedString> {
+        return self.rules.patterns();
+    }
+
+    // invalid and ignored classes, characters in them never start a keyword or an identifier
+    pub fn classes(&self) -> Vec<CharacterClass> {
+        return self.classes.clone();
+    }
+
     pub fn find(&self, character_stack: &mut CharacterStack, tokens: &mut Vec<Token>, complete: bool) -> Status<bool> {
         if let Some((matched, action)) = self.rules.check_stack(character_stack) {
             match action {
@@ -0,0 +1,100 @@
+use internal::*;
+use debug::*;
+
+use tokenize::Token;
+
+pub struct PatternTokenizer {
+    patterns:       Vec<(SharedString, Pattern, i64)>,
+}
+
+impl PatternTokenizer {
+
+    pub fn new(settings: &Data, _character_stack: &mut CharacterStack, variant_registry: &mut VariantRegistry) -> Status<Self> {
+        ensure!(settings.is_map(), ExpectedFound, expected_list!["map"], settings.clone());
+        let mut patterns: Vec<(SharedString, Pattern, i64)> = Vec::new();
+
+        if let Some(pattern_lookup) = confirm!(settings.index(&keyword!("patterns"))) {
+            ensure!(pattern_lookup.is_map(), ExpectedFound, expected_list!["map"], pattern_lookup.clone());
+
+            for (name, definition) in confirm!(pattern_lookup.pairs()).into_iter() {
+                let name = unpack_identifier!(&name);
+                let (source, priority) = match definition.is_map() {
+                    true => {
+                        let source = expect!(confirm!(definition.index(&keyword!("pattern"))), string!("pattern {} is missing a pattern", name));
+                        let priority = match confirm!(definition.index(&keyword!("priority"))) {
+                            Some(priority) => unpack_integer!(&priority),
+                            None => 0,
+                        };
+                        (unpack_string!(&source), priority)
+                    },
+                    false => (unpack_string!(&definition), 0),
+                };
+
+                let pattern = confirm!(Pattern::new(source), Tag, string!(String, name.clone()));
+                variant_registry.register_pattern(name.clone());
+
+                // higher priorities are checked first, equal priorities keep their order
+                let index = patterns.iter().position(|(_, _, other)| *other < priority).unwrap_or(patterns.len());
+                patterns.insert(index, (name, pattern, priority));
+            }
+        }
+
+        return success!(Self {
+            patterns:       patterns,
+        });
+    }
+
+    // a match may not end inside of a word, so 2024-01-01x is not a date
+    fn splits_word(character_stack: &mut CharacterStack, length: usize) -> bool {
+        let last = character_stack.peek(length - 1).unwrap();
+        match character_stack.peek(length) {
+            Some(next) => return !character_stack.is_breaking(last) && !character_stack.is_breaking(next),
+            None => return false,
+        }
+    }
+
+    pub fn names(&self) -> Vec<SharedString> {
+        return self.patterns.iter().map(|(name, _, _)| name.clone()).collect();
+    }
+
+    // the first pattern matching the start of the literal
+    pub fn claims(&self, literal: &SharedString) -> Option<SharedString> {
+        let mut character_stack = CharacterStack::new(literal.clone(), None);
+        return self.patterns.iter().find(|(_, pattern, _)| pattern.longest(&mut character_stack).is_some()).map(|(name, _, _)| name.clone());
+    }
+
+    pub fn find(&self, character_stack: &mut CharacterStack, tokens: &mut Vec<Token>) -> Status<bool> {
+        let mut found: Option<(&SharedString, usize, i64)> = None;
+
+        for (name, pattern, priority) in self.patterns.iter() {
+            if let Some((_, _, found_priority)) = found {
+                if *priority < found_priority {
+                    break;
+                }
+            }
+
+            if let Some(length) = pattern.longest(character_stack) {
+                if PatternTokenizer::splits_word(character_stack, length) {
+                    continue;
+                }
+
+                if found.map(|(_, found_length, _)| length > found_length).unwrap_or(true) {
+                    found = Some((name, length, *priority));
+                }
+            }
+        }
+
+        let (name, length, _) = match found {
+            Some(found) => found,
+            None => return success!(false),
+        };
+
+        let mut text = SharedString::new();
+        for _ in 0..length {
+            text.push(character_stack.pop().unwrap());
+        }
+
+        tokens.push(Token::new(TokenType::Custom(name.clone(), text), character_stack.final_positions()));
+        return success!(true);
+    }
+}
@@ -3,9 +3,17 @@ use debug::*;
 
 use tokenize::Token;
 
+// delimiter and start of the interrupted string and the number of nested pairs opened inside the expression
+pub type Interpolation = (usize, usize, usize);
+
 pub struct StringTokenizer {
     delimiters:     Vec<(SharedString, SharedString)>,
     replace:        Vec<(SharedString, SharedString)>,
+    unicode:        Vec<SharedString>,
+    interpolation:  Option<(SharedString, SharedString)>,
+    raw:            Vec<(SharedString, SharedString, SharedString, SharedString)>,
+    heredocs:       Vec<SharedString>,
+    strip:          bool,
 }
 
 impl StringTokenizer {
@@ -41,35 +49,354 @@ impl StringTokenizer {
             }
         }
 
+        let mut unicode = Vec::new();
+        if let Some(unicode_list) = confirm!(settings.index(&keyword!("unicode"))) {
+            for prefix in unpack_list!(&unicode_list).iter() {
+                let prefix = unpack_literal!(prefix);
+                ensure!(!prefix.is_empty(), EmptyLiteral);
+                push_by_length!(unicode, prefix);
+            }
+        }
+
+        let interpolation = match confirm!(settings.index(&keyword!("interpolation"))) {
+            Some(interpolation) => {
+                let interpolation = unpack_list!(&interpolation);
+                ensure!(interpolation.len() == 2, InvalidItemCount, integer!(2), integer!(interpolation.len() as i64));
+                let start_delimiter = unpack_literal!(&interpolation[0]);
+                let end_delimiter = unpack_literal!(&interpolation[1]);
+                ensure!(!start_delimiter.is_empty(), EmptyLiteral);
+                ensure!(!end_delimiter.is_empty(), EmptyLiteral);
+                variant_registry.has_interpolation = true;
+                Some((start_delimiter, end_delimiter))
+            }
+            None => None,
+        };
+
+        let mut raw = Vec::new();
+        if let Some(raw_list) = confirm!(settings.index(&keyword!("raw"))) {
+            for fence_list in unpack_list!(&raw_list).iter() {
+                let fence_list = unpack_list!(fence_list);
+                ensure!(fence_list.len() == 4, InvalidItemCount, integer!(4), integer!(fence_list.len() as i64));
+                let prefix = unpack_literal!(&fence_list[0]);
+                let fence = unpack_literal!(&fence_list[1]);
+                let start_delimiter = unpack_literal!(&fence_list[2]);
+                let end_delimiter = unpack_literal!(&fence_list[3]);
+                ensure!(!fence.is_empty(), EmptyLiteral);
+                ensure!(!start_delimiter.is_empty(), EmptyLiteral);
+                ensure!(!end_delimiter.is_empty(), EmptyLiteral);
+                raw.push((prefix, fence, start_delimiter, end_delimiter));
+            }
+        }
+
+        // heredoc introducers are not registered as signatures, since they usually share them with shift operators
+        let mut heredocs = Vec::new();
+        if let Some(heredoc_list) = confirm!(settings.index(&keyword!("heredocs"))) {
+            for introducer in unpack_list!(&heredoc_list).iter() {
+                let introducer = unpack_literal!(introducer);
+                ensure!(!introducer.is_empty(), EmptyLiteral);
+                push_by_length!(heredocs, introducer);
+            }
+        }
+
+        let strip = match confirm!(settings.index(&keyword!("strip_indentation"))) {
+            Some(strip) => unpack_boolean!(&strip),
+            None => false,
+        };
+
         return success!(Self {
             delimiters:     delimiters,
             replace:        replace,
+            unicode:        unicode,
+            interpolation:  interpolation,
+            raw:            raw,
+            heredocs:       heredocs,
+            strip:          strip,
         });
     }
 
-    pub fn find(&self, character_stack: &mut CharacterStack, tokens: &mut Vec<Token>) -> Status<bool> {
-        for (start_delimiter, end_delimiter) in self.delimiters.iter() {
-            if character_stack.check_string(&start_delimiter) {
-                let mut string = SharedString::new();
-                'check: while !character_stack.check_string(&end_delimiter) {
+    fn is_indentation(character: &Character) -> bool {
+        return character.code() == ' ' as u32 || character.code() == '\t' as u32;
+    }
 
-                    if character_stack.is_empty() {
-                        let error = Error::UnterminatedToken(identifier!("string"));
-                        tokens.push(Token::new(TokenType::Invalid(error), character_stack.final_positions()));
-                        return success!(true);
-                    }
+    // blank lines do not count towards the common indentation
+    fn strip_indentation(&self, string: SharedString) -> SharedString {
+        if !self.strip {
+            return string;
+        }
+
+        let lines: Vec<Vec<Character>> = string.split(&SharedString::from("\n"), false).iter().map(|line| line.chars().cloned().collect()).collect();
+        let common = lines.iter()
+            .filter(|line| !line.iter().all(StringTokenizer::is_indentation))
+            .map(|line| line.iter().take_while(|character| StringTokenizer::is_indentation(character)).count())
+            .min()
+            .unwrap_or(0);
+
+        let mut stripped = SharedString::new();
+        for (index, line) in lines.iter().enumerate() {
+            if index > 0 {
+                stripped.push(Character::from_char('\n'));
+            }
+            line.iter().skip(common).for_each(|character| stripped.push(*character));
+        }
+        return stripped;
+    }
+
+    fn read_hex(character_stack: &mut CharacterStack) -> Option<u32> {
+        let mut code = 0;
+        for _ in 0..4 {
+            let digit = character_stack.peek(0).and_then(|character| std::char::from_u32(character.code())).and_then(|character| character.to_digit(16))?;
+            code = code * 16 + digit;
+            character_stack.advance(1);
+        }
+        return Some(code);
+    }
+
+    // surrogate pairs are combined into a single code point
+    fn read_unicode(character_stack: &mut CharacterStack, prefix: &SharedString) -> Status<Character> {
+        let code = expect!(StringTokenizer::read_hex(character_stack), InvalidEscapeSequence, string!(String, prefix.clone()));
+        if code < 0xD800 || code >= 0xE000 {
+            return success!(Character::from_code(code));
+        }
+
+        ensure!(code < 0xDC00 && character_stack.check_string(prefix), InvalidEscapeSequence, string!("{}{:04x}", prefix, code));
+        let low = expect!(StringTokenizer::read_hex(character_stack), InvalidEscapeSequence, string!(String, prefix.clone()));
+        ensure!(low >= 0xDC00 && low < 0xE000, InvalidEscapeSequence, string!("{}{:04x}", prefix, low));
+        return success!(Character::from_code(0x10000 + ((code - 0xD800) << 10) + (low - 0xDC00)));
+    }
+
+    fn find_raw(&self, character_stack: &mut CharacterStack, tokens: &mut Vec<Token>) -> bool {
+        for (prefix, fence, start_delimiter, end_delimiter) in self.raw.iter() {
+            character_stack.save();
+            if !character_stack.check_string(prefix) {
+                character_stack.restore();
+                continue;
+            }
+
+            let mut fences = 0;
+            while character_stack.check_string(fence) {
+                fences += 1;
+            }
+
+            if !character_stack.check_string(start_delimiter) {
+                character_stack.restore();
+                continue;
+            }
+            character_stack.drop();
+
+            let mut string = SharedString::new();
+            loop {
+                if character_stack.is_empty() {
+                    let error = Error::UnterminatedToken(identifier!("string"));
+                    tokens.push(Token::new(TokenType::Invalid(error), character_stack.final_positions()));
+                    return true;
+                }
 
-                    for (from, to) in self.replace.iter() {
-                        if character_stack.check_string(&from) {
-                            string.push_str(to);
-                            continue 'check;
-                        }
+                character_stack.save();
+                if character_stack.check_string(end_delimiter) && (0..fences).all(|_| character_stack.check_string(fence)) {
+                    character_stack.drop();
+                    break;
+                }
+                character_stack.restore();
+                string.push(character_stack.pop().unwrap());
+            }
+
+            tokens.push(Token::new(TokenType::String(self.strip_indentation(string)), character_stack.final_positions()));
+            return true;
+        }
+        return false;
+    }
+
+    // a carriage return in front of the line break belongs to the line ending and is not part of the line
+    fn read_line(character_stack: &mut CharacterStack) -> SharedString {
+        let mut line = SharedString::new();
+        while let Some(character) = character_stack.peek(0) {
+            if character.is_newline() {
+                break;
+            }
+            line.push(character);
+            character_stack.advance(1);
+        }
+
+        if line.reverse_chars().next().map(|character| character.code() == '\r' as u32).unwrap_or(false) {
+            line.pop();
+        }
+        return line;
+    }
+
+    // the terminator has to be the last word of the opening line, the literal ends before the line holding only the terminator
+    // once such an opening line was found the heredoc is not given back to the operators, even if the source ends right after it
+    fn find_heredoc(&self, character_stack: &mut CharacterStack, tokens: &mut Vec<Token>) -> bool {
+        for introducer in self.heredocs.iter() {
+            character_stack.save();
+            if !character_stack.check_string(introducer) {
+                character_stack.restore();
+                continue;
+            }
+
+            let mut terminator = SharedString::new();
+            while let Some(character) = character_stack.peek(0) {
+                if character_stack.is_breaking(character) || character.code() == '\r' as u32 {
+                    break;
+                }
+                terminator.push(character);
+                character_stack.advance(1);
+            }
+
+            let rest = StringTokenizer::read_line(character_stack);
+            if terminator.is_empty() || !rest.chars().all(StringTokenizer::is_indentation) {
+                character_stack.restore();
+                continue;
+            }
+            character_stack.drop();
+            character_stack.check('\n');
+
+            let mut lines = Vec::new();
+            loop {
+                if character_stack.is_empty() {
+                    let error = Error::UnterminatedToken(identifier!("string"));
+                    tokens.push(Token::new(TokenType::Invalid(error), character_stack.final_positions()));
+                    return true;
+                }
+
+                let line = StringTokenizer::read_line(character_stack);
+                let compare: SharedString = match self.strip {
+                    true => line.chars().skip_while(|character| StringTokenizer::is_indentation(character)).cloned().collect(),
+                    false => line.clone(),
+                };
+
+                if compare == terminator {
+                    break;
+                }
+
+                character_stack.check('\n');
+                lines.push(line);
+            }
+
+            let mut string = SharedString::new();
+            for (index, line) in lines.iter().enumerate() {
+                if index > 0 {
+                    string.push(Character::from_char('\n'));
+                }
+                string.push_str(line);
+            }
+
+            tokens.push(Token::new(TokenType::String(self.strip_indentation(string)), character_stack.final_positions()));
+            return true;
+        }
+        return false;
+    }
+
+    // an unterminated string is reported from its start delimiter, even if it was resumed after an interpolation
+    fn scan(&self, character_stack: &mut CharacterStack, tokens: &mut Vec<Token>, delimiter: usize, start: usize, resumed: bool, interpolations: &mut Vec<Interpolation>) {
+        let end_delimiter = &self.delimiters[delimiter].1;
+        let mut string = SharedString::new();
+        let mut invalid = None;
+
+        'check: while !character_stack.check_string(end_delimiter) {
+
+            if character_stack.is_empty() {
+                let end = character_stack.current_position();
+                let error = Error::UnterminatedToken(identifier!("string"));
+                tokens.push(Token::new(TokenType::Invalid(error), vec![Position::new(end.file, start, end.start)]));
+                return;
+            }
+
+            for prefix in self.unicode.iter() {
+                if character_stack.check_string(prefix) {
+                    match StringTokenizer::read_unicode(character_stack, prefix) {
+                        Status::Success(character) => string.push(character),
+                        Status::Error(error) => invalid = Some(error),
                     }
+                    continue 'check;
+                }
+            }
 
-                    string.push(character_stack.pop().unwrap());
+            for (from, to) in self.replace.iter() {
+                if character_stack.check_string(&from) {
+                    string.push_str(to);
+                    continue 'check;
                 }
+            }
+
+            if let Some((start_interpolation, _)) = &self.interpolation {
+                let part_positions = character_stack.final_positions();
+                let part_end = character_stack.current_position();
 
-                tokens.push(Token::new(TokenType::String(string), character_stack.final_positions()));
+                if character_stack.check_string(start_interpolation) {
+                    let start_positions = vec![Position::new(part_end.file, part_end.start, character_stack.current_position().start)];
+                    tokens.push(Token::new(TokenType::StringPart(string), part_positions));
+                    tokens.push(Token::new(TokenType::InterpolationStart, start_positions));
+                    interpolations.push((delimiter, start, 0));
+                    return;
+                }
+            }
+
+            string.push(character_stack.pop().unwrap());
+        }
+
+        if let Some(error) = invalid {
+            tokens.push(Token::new(TokenType::Invalid(error), character_stack.final_positions()));
+            return;
+        }
+
+        match resumed {
+            true => tokens.push(Token::new(TokenType::StringPart(string), character_stack.final_positions())),
+            false => tokens.push(Token::new(TokenType::String(string), character_stack.final_positions())),
+        }
+    }
+
+    // checks the start of every token inside an interpolation, the string continues once the end delimiter is found outside of nested pairs
+    pub fn close(&self, character_stack: &mut CharacterStack, tokens: &mut Vec<Token>, interpolations: &mut Vec<Interpolation>) -> bool {
+        let (start_interpolation, end_interpolation) = match &self.interpolation {
+            Some(interpolation) => interpolation,
+            None => return false,
+        };
+
+        let (delimiter, start, depth) = match interpolations.last_mut() {
+            Some(interpolation) => interpolation,
+            None => return false,
+        };
+
+        character_stack.save();
+        let closing = character_stack.check_string(end_interpolation);
+        character_stack.restore();
+
+        if closing && *depth > 0 {
+            *depth -= 1;
+            return false;
+        }
+
+        if !closing {
+            if character_stack.peek(0) == start_interpolation.chars().last().cloned() {
+                *depth += 1;
+            }
+            return false;
+        }
+
+        let (delimiter, start) = (*delimiter, *start);
+        interpolations.pop();
+        character_stack.start_positions();
+        character_stack.check_string(end_interpolation);
+        tokens.push(Token::new(TokenType::InterpolationEnd, character_stack.final_positions()));
+
+        character_stack.start_positions();
+        self.scan(character_stack, tokens, delimiter, start, true, interpolations);
+        return true;
+    }
+
+    pub fn delimiters(&self) -> Vec<SharedString> {
+        return self.delimiters.iter().map(|(start_delimiter, _)| start_delimiter.clone()).collect();
+    }
+
+    pub fn find(&self, character_stack: &mut CharacterStack, tokens: &mut Vec<Token>, interpolations: &mut Vec<Interpolation>) -> Status<bool> {
+        if self.find_raw(character_stack, tokens) || self.find_heredoc(character_stack, tokens) {
+            return success!(true);
+        }
+
+        let start = character_stack.current_position().start;
+        for (delimiter, (start_delimiter, _)) in self.delimiters.iter().enumerate() {
+            if character_stack.check_string(&start_delimiter) {
+                self.scan(character_stack, tokens, delimiter, start, false, interpolations);
                 return success!(true);
             }
         }